@@ -0,0 +1,148 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    providers::JsonRpcError,
+    types::{Bytes, H256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::transport::{is_ipc_path, is_ws_url};
+
+/// Sends JSON-RPC batch requests, which the ethers providers can't, by
+/// posting the request array to the HTTP endpoint directly
+#[derive(Debug, Clone)]
+pub struct BatchSender {
+    http: reqwest::Client,
+    url: reqwest::Url,
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: [&'a Bytes; 1],
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<H256>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+/// Nodes answer a batch with an array, or with a single error object when
+/// they reject the batch as a whole (e.g. batches disabled or too large)
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BatchResponse {
+    Batch(Vec<Response>),
+    Single(Response),
+}
+
+impl BatchSender {
+    /// A sender for the HTTP endpoint `rpc_url`
+    pub fn new(rpc_url: &str) -> Result<Self> {
+        if is_ws_url(rpc_url) || is_ipc_path(rpc_url) {
+            bail!("batch submission needs an HTTP endpoint, not {}", rpc_url);
+        }
+        let url = rpc_url.parse().with_context(|| format!("invalid RPC URL {}", rpc_url))?;
+        Ok(Self { http: reqwest::Client::new(), url })
+    }
+
+    /// Sends `raws` as one batch of `eth_sendRawTransaction` calls. Returns
+    /// the hash or the node's error of every entry in the order given, or
+    /// an error when the batch as a whole failed.
+    pub async fn send_raw_transactions(&self, raws: &[Bytes]) -> Result<Vec<Result<H256>>> {
+        let requests = raws
+            .iter()
+            .enumerate()
+            .map(|(i, raw)| Request {
+                jsonrpc: "2.0",
+                id: i as u64,
+                method: "eth_sendRawTransaction",
+                params: [raw],
+            })
+            .collect::<Vec<_>>();
+
+        let response = self.http.post(self.url.clone()).json(&requests).send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        // Nodes rejecting a batch with an HTTP error usually say why in the body
+        if !status.is_success() {
+            bail!("batch rejected with HTTP {}: {}", status, String::from_utf8_lossy(&body).trim());
+        }
+        parse_responses(&body, raws.len())
+    }
+}
+
+/// Parses the response to a batch of `len` requests with ids `0..len` into
+/// one result per request, in id order
+fn parse_responses(body: &[u8], len: usize) -> Result<Vec<Result<H256>>> {
+    let responses = match serde_json::from_slice(body)
+        .with_context(|| format!("invalid batch response: {}", String::from_utf8_lossy(body)))?
+    {
+        BatchResponse::Batch(responses) => responses,
+        BatchResponse::Single(Response { error: Some(error), .. }) => bail!("batch rejected: {}", error),
+        BatchResponse::Single(_) => bail!("batch answered with a single response"),
+    };
+
+    // Entries may come back in any order, so match them up by id
+    let mut by_id = responses
+        .into_iter()
+        .filter_map(|response| response.id.map(|id| (id, response)))
+        .collect::<HashMap<_, _>>();
+    Ok((0..len as u64)
+        .map(|id| match by_id.remove(&id) {
+            Some(Response { error: Some(error), .. }) => Err(anyhow!("{}", error)),
+            Some(Response { result: Some(hash), .. }) => Ok(hash),
+            Some(_) => Err(anyhow!("entry {} has neither a result nor an error", id)),
+            None => Err(anyhow!("no response for entry {}", id)),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(byte: u8) -> H256 {
+        H256::repeat_byte(byte)
+    }
+
+    #[test]
+    fn matches_responses_by_id() {
+        let body = format!(
+            r#"[{{"jsonrpc":"2.0","id":2,"result":"{:?}"}},{{"jsonrpc":"2.0","id":0,"result":"{:?}"}},{{"jsonrpc":"2.0","id":1,"result":"{:?}"}}]"#,
+            hash(3),
+            hash(1),
+            hash(2)
+        );
+        let results = parse_responses(body.as_bytes(), 3).unwrap();
+        let hashes = results.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(hashes, vec![hash(1), hash(2), hash(3)]);
+    }
+
+    #[test]
+    fn keeps_per_entry_errors() {
+        let body = format!(
+            r#"[{{"jsonrpc":"2.0","id":1,"error":{{"code":-32000,"message":"nonce too low"}}}},{{"jsonrpc":"2.0","id":0,"result":"{:?}"}}]"#,
+            hash(1)
+        );
+        let results = parse_responses(body.as_bytes(), 3).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &hash(1));
+        assert!(results[1].as_ref().unwrap_err().to_string().contains("nonce too low"));
+        assert_eq!(results[2].as_ref().unwrap_err().to_string(), "no response for entry 2");
+    }
+
+    #[test]
+    fn single_error_rejects_the_batch() {
+        let body = r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"batch too large"}}"#;
+        let error = parse_responses(body.as_bytes(), 2).unwrap_err();
+        assert!(error.to_string().contains("batch too large"), "{}", error);
+    }
+}
+
@@ -0,0 +1,270 @@
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use std::{env, path::PathBuf, sync::Arc};
+
+use crate::{
+    transport::Transport,
+    wallet::{
+        decrypt_keystores, derive_wallets, keystore_files, prompt_password, read_password_file, Secret,
+        DEFAULT_DERIVATION_PATH,
+    },
+};
+
+/// Signing client shared by all the latency tools, over HTTP or WebSocket
+/// depending on the RPC URL
+pub type Client = SignerMiddleware<Provider<Transport>, LocalWallet>;
+
+/// A connected signing client together with the details the tools print
+pub struct Connection {
+    pub client: Arc<Client>,
+    pub rpc_url: String,
+    pub chain_id: U256,
+    pub address: Address,
+}
+
+/// Builds `Connection`s from an RPC URL and the sender wallets, which come
+/// from a mnemonic, else encrypted keystore files, else plain private keys.
+/// Secrets are redacted from the `Debug` output.
+#[derive(Debug, Default, Clone)]
+pub struct ClientBuilder {
+    rpc_url: Option<String>,
+    private_keys: Vec<Secret>,
+    mnemonic: Option<Secret>,
+    derivation_path: Option<String>,
+    keystore: Option<PathBuf>,
+    keystore_password: Option<Secret>,
+    password_file: Option<PathBuf>,
+    mnemonic_count: Option<u32>,
+    wallet_count: Option<u32>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `RPC_PROVIDER`, `PRIVATE_KEY_1`, `PRIVATE_KEY_2`, ... (stopping
+    /// at the first missing index), the optional `MNEMONIC`, `MNEMONIC_PATH`
+    /// and `MNEMONIC_COUNT`, and the optional `KEYSTORE` and
+    /// `KEYSTORE_PASSWORD_FILE` from the environment
+    pub fn from_env() -> Self {
+        let private_keys = (1..)
+            .map_while(|i| env::var(format!("PRIVATE_KEY_{}", i)).ok())
+            .map(Secret::from)
+            .collect();
+        Self {
+            rpc_url: env::var("RPC_PROVIDER").ok(),
+            private_keys,
+            mnemonic: env::var("MNEMONIC").ok().map(Secret::from),
+            derivation_path: env::var("MNEMONIC_PATH").ok(),
+            keystore: env::var_os("KEYSTORE").map(PathBuf::from),
+            keystore_password: None,
+            password_file: env::var_os("KEYSTORE_PASSWORD_FILE").map(PathBuf::from),
+            mnemonic_count: env::var("MNEMONIC_COUNT").ok().and_then(|count| count.parse().ok()),
+            wallet_count: None,
+        }
+    }
+
+    pub fn rpc_url(mut self, rpc_url: impl Into<String>) -> Self {
+        self.rpc_url = Some(rpc_url.into());
+        self
+    }
+
+    /// Uses a single private key as the only sender wallet
+    pub fn private_key(self, private_key: impl Into<String>) -> Self {
+        self.private_keys([private_key])
+    }
+
+    /// Uses one sender wallet per private key, in order
+    pub fn private_keys<I, S>(mut self, private_keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.private_keys = private_keys.into_iter().map(|key| Secret::new(key)).collect();
+        self.mnemonic = None;
+        self.keystore = None;
+        self
+    }
+
+    /// Derives the sender wallets from a BIP-39 mnemonic instead of using
+    /// the keystore or private keys
+    pub fn mnemonic(mut self, phrase: impl Into<String>) -> Self {
+        self.mnemonic = Some(Secret::new(phrase));
+        self
+    }
+
+    /// Decrypts the sender wallets from a Web3 Secret Storage keystore file,
+    /// or from every file in a keystore directory, instead of using the
+    /// private keys
+    pub fn keystore(mut self, path: impl Into<PathBuf>) -> Self {
+        self.keystore = Some(path.into());
+        self.mnemonic = None;
+        self
+    }
+
+    /// Keystore password. Without it the password is read from the password
+    /// file, or prompted for on the terminal.
+    pub fn keystore_password(mut self, password: impl Into<String>) -> Self {
+        self.keystore_password = Some(Secret::new(password));
+        self
+    }
+
+    /// File holding the keystore password
+    pub fn password_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.password_file = Some(path.into());
+        self
+    }
+
+    /// Base BIP-44 path the mnemonic wallets are derived under, wallet `i`
+    /// being `<path>/i` [default: m/44'/60'/0'/0]
+    pub fn derivation_path(mut self, path: impl Into<String>) -> Self {
+        self.derivation_path = Some(path.into());
+        self
+    }
+
+    /// Number of wallets derived from the mnemonic when `wallet_count` is
+    /// not set [default: 1]. Private keys and keystores ignore it.
+    pub fn mnemonic_count(mut self, count: u32) -> Self {
+        self.mnemonic_count = Some(count);
+        self
+    }
+
+    /// Number of sender wallets: how many to derive from the mnemonic
+    /// [default: `mnemonic_count`], or how many of the private keys or
+    /// keystore files to use [default: all]
+    pub fn wallet_count(mut self, count: u32) -> Self {
+        self.wallet_count = Some(count);
+        self
+    }
+
+    /// Connects to the provider, fetches the chain id and wraps the first
+    /// wallet in a `SignerMiddleware` bound to that chain
+    pub async fn build(self) -> Result<Connection> {
+        let mut connections = self.wallet_count(1).build_all().await?;
+        Ok(connections.remove(0))
+    }
+
+    /// Like `build`, but returns one `Connection` per sender wallet, all
+    /// sharing the same provider (and WebSocket connection)
+    pub async fn build_all(self) -> Result<Vec<Connection>> {
+        let rpc_url = self.rpc_url.clone().ok_or_else(|| anyhow!("RPC_PROVIDER must be set"))?;
+        let wallets = self.wallets()?;
+
+        let provider = Provider::new(Transport::connect(&rpc_url).await?);
+        let chain_id = provider.get_chainid().await?;
+
+        Ok(wallets
+            .into_iter()
+            .map(|wallet| {
+                let address = wallet.address();
+                let wallet = wallet.with_chain_id(chain_id.as_u64());
+                Connection {
+                    client: Arc::new(SignerMiddleware::new(provider.clone(), wallet)),
+                    rpc_url: rpc_url.clone(),
+                    chain_id,
+                    address,
+                }
+            })
+            .collect())
+    }
+
+    fn wallets(&self) -> Result<Vec<LocalWallet>> {
+        if self.wallet_count == Some(0) {
+            bail!("at least one sender wallet is required");
+        }
+
+        if let Some(phrase) = &self.mnemonic {
+            let path = self.derivation_path.as_deref().unwrap_or(DEFAULT_DERIVATION_PATH);
+            let count = self.wallet_count.or(self.mnemonic_count).unwrap_or(1);
+            return derive_wallets(phrase, path, count);
+        }
+
+        if let Some(keystore) = &self.keystore {
+            let files = keystore_files(keystore)?;
+            let files = &files[..self.limit(files.len(), "keystore files")?];
+            let password = match (&self.keystore_password, &self.password_file) {
+                (Some(password), _) => password.clone(),
+                (None, Some(password_file)) => read_password_file(password_file)?,
+                (None, None) => prompt_password(keystore)?,
+            };
+            return decrypt_keystores(files, &password);
+        }
+
+        if self.private_keys.is_empty() {
+            bail!("PRIVATE_KEY_1, MNEMONIC or KEYSTORE must be set");
+        }
+        let count = self.limit(self.private_keys.len(), "private keys")?;
+
+        self.private_keys[..count]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                key.expose()
+                    .parse::<LocalWallet>()
+                    .map_err(|e| anyhow!("private key #{} is invalid: {}", i + 1, e))
+            })
+            .collect()
+    }
+
+    /// Number of the `available` keys to use, checked against `wallet_count`
+    fn limit(&self, available: usize, what: &str) -> Result<usize> {
+        match self.wallet_count {
+            Some(count) if count as usize > available => bail!(
+                "{} sender wallets requested but only {} {} are configured",
+                count,
+                available,
+                what
+            ),
+            Some(count) => Ok(count as usize),
+            None => Ok(available),
+        }
+    }
+}
+
+impl Connection {
+    /// Prints the connection details shown at the start of every run
+    pub fn print_info(&self) {
+        println!("RPC URL: {}", self.rpc_url);
+        println!("Transport: {}", self.client.provider().as_ref());
+        println!("Chain ID: {}", self.chain_id);
+        println!("Wallet address: {}", self.address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const KEY_2: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn mnemonic_count_does_not_limit_private_keys() {
+        env::set_var("MNEMONIC_COUNT", "200");
+        let wallets = ClientBuilder::from_env().private_keys([KEY_1, KEY_2]).wallets().unwrap();
+        assert_eq!(wallets.len(), 2);
+    }
+
+    #[test]
+    fn mnemonic_count_sets_the_derived_wallets() {
+        let builder = ClientBuilder::new().mnemonic(PHRASE).mnemonic_count(3);
+        assert_eq!(builder.clone().wallets().unwrap().len(), 3);
+        assert_eq!(builder.wallet_count(2).wallets().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn wallet_count_limits_private_keys() {
+        let builder = ClientBuilder::new().private_keys([KEY_1, KEY_2]);
+        assert_eq!(builder.clone().wallet_count(1).wallets().unwrap().len(), 1);
+        let error = builder.clone().wallet_count(3).wallets().unwrap_err();
+        assert_eq!(error.to_string(), "3 sender wallets requested but only 2 private keys are configured");
+        assert!(builder.wallet_count(0).wallets().is_err());
+    }
+}
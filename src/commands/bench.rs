@@ -0,0 +1,310 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::Args;
+use ethers::providers::Middleware;
+use rust_web3_utils::{
+    confirm::Confirmer,
+    gas::{Fees, GasStrategy},
+    report::{duration_ms, BenchReport, EndpointRun, ReportFormat, RunReport, RESULTS_DIR},
+    runner::{run_sequential, SequentialRun},
+    transport::is_ipc_path,
+    tx::TxType,
+    Config, Connection, Method, Profile,
+};
+use std::{path::Path, sync::Arc, time::Instant};
+use tokio::sync::Barrier;
+
+use super::{tx_count, tx_type, ConfirmArgs, GasArgs};
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Endpoint to benchmark, repeat once per endpoint: a profile name,
+    /// optionally preceded by `LABEL=`, or an RPC URL or IPC socket path
+    /// optionally preceded by `KEY_ENV=` naming the variable holding that
+    /// endpoint's private key. Every endpoint needs its own wallet.
+    #[arg(long, short = 't', value_name = "TARGET", required = true)]
+    pub target: Vec<String>,
+
+    /// Submission method, defaults to the profile's method or async
+    #[arg(long, short = 'm', value_enum)]
+    pub method: Option<Method>,
+
+    /// Number of transactions per endpoint, defaults to the profile's tx_count or 10
+    #[arg(long, short = 'c', value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: Option<u64>,
+
+    /// Transaction type sent to every endpoint, defaults to each target
+    /// profile's tx_type or eip1559
+    #[arg(long, value_enum)]
+    pub tx_type: Option<TxType>,
+
+    /// Gas flags, applied on top of each target profile's gas policy
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// Confirmation flags, applied on top of each target profile's confirm mode
+    #[command(flatten)]
+    pub confirm: ConfirmArgs,
+
+    /// Name of the test, used in the report title and file name
+    #[arg(long, short = 'n', value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Report formats to write into results/, comma separated
+    #[arg(long, short = 'f', value_enum, value_delimiter = ',', default_value = "markdown,json")]
+    pub format: Vec<ReportFormat>,
+}
+
+/// A connected endpoint ready to run the workload
+struct Endpoint {
+    label: String,
+    conn: Connection,
+    starting_nonce: u64,
+    tx_type: TxType,
+    gas_strategy: GasStrategy,
+    fees: Fees,
+    confirmer: Confirmer,
+}
+
+pub async fn run(args: BenchArgs, config: &Config, profile: &Profile) -> Result<()> {
+    if args.target.len() < 2 {
+        bail!("bench needs at least two --target endpoints");
+    }
+
+    let method = args.method.or(profile.method).unwrap_or(Method::Async);
+    let num_transactions = tx_count(args.count, profile);
+
+    // Connect every endpoint and make the setup RPC calls before any timing starts
+    let mut endpoints: Vec<Endpoint> = Vec::with_capacity(args.target.len());
+    for target in &args.target {
+        let (label, target_profile) = resolve_target(target, config)?;
+        println!("\n[{}]", label);
+
+        let conn = target_profile
+            .client_builder()?
+            .build()
+            .await
+            .with_context(|| format!("failed to connect to {}", label))?;
+        conn.print_info();
+
+        if let Some(other) = endpoints
+            .iter()
+            .find(|e| e.conn.address == conn.address && e.conn.chain_id == conn.chain_id)
+        {
+            bail!("{} and {} use the same wallet {:?} on chain {}; give each endpoint its own key",
+                  other.label, label, conn.address, conn.chain_id);
+        }
+
+        let starting_nonce = conn.client.get_transaction_count(conn.address, None).await?.as_u64();
+        println!("Starting nonce: {}", starting_nonce);
+        let (gas_strategy, fees) = args.gas.fees(&conn, &target_profile).await?;
+        let tx_type = tx_type(args.tx_type, &target_profile);
+        println!("Transaction type: {}", tx_type);
+        let confirmer = match method {
+            Method::Async => args.confirm.confirmer(&conn, &target_profile).await?,
+            _ => Confirmer::default(),
+        };
+
+        endpoints.push(Endpoint { label, conn, starting_nonce, tx_type, gas_strategy, fees, confirmer });
+    }
+
+    if let Some(test_name) = &args.name {
+        println!("\nTest name: {}", test_name);
+    }
+    println!("Transaction method: {}", method);
+    println!("\nSending {} transactions sequentially to each of {} endpoints at the same time...",
+             num_transactions, endpoints.len());
+
+    // Every task waits on the barrier so the timed loops start together
+    let barrier = Arc::new(Barrier::new(endpoints.len()));
+    let started_at = Utc::now();
+    let handles = endpoints
+        .into_iter()
+        .map(|endpoint| {
+            let barrier = barrier.clone();
+            tokio::spawn(async move {
+                let run = SequentialRun {
+                    method,
+                    count: num_transactions,
+                    first_index: 0,
+                    starting_nonce: endpoint.starting_nonce,
+                    tx_type: endpoint.tx_type,
+                    fees: endpoint.fees,
+                    confirmer: endpoint.confirmer.clone(),
+                    verbose: false,
+                };
+
+                barrier.wait().await;
+                let start = Instant::now();
+                let mut transactions = Vec::with_capacity(num_transactions as usize);
+                let outcome = run_sequential(&endpoint.conn, &run, &mut transactions).await;
+                let elapsed = start.elapsed();
+
+                (endpoint, transactions, outcome, elapsed)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut runs = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let (endpoint, transactions, outcome, elapsed) = handle.await?;
+        if let Err(e) = outcome {
+            // Keep the other endpoints' results and what this one confirmed
+            println!("[{}] run aborted: {:#}", endpoint.label, e);
+        }
+        println!("[{}] {}/{} transactions confirmed in {:?}",
+                 endpoint.label, transactions.len(), num_transactions, elapsed);
+
+        runs.push(EndpointRun {
+            label: endpoint.label,
+            report: RunReport {
+                test_name: args.name.clone(),
+                timestamp: started_at,
+                rpc_url: endpoint.conn.rpc_url.clone(),
+                chain_id: endpoint.conn.chain_id.as_u64(),
+                wallet: endpoint.conn.address,
+                senders: vec![endpoint.conn.address],
+                method,
+                tx_type: Some(endpoint.tx_type),
+                gas_price_wei: endpoint.fees.gas_price.to_string(),
+                gas_strategy: Some(endpoint.gas_strategy),
+                max_fee_per_gas_wei: Some(endpoint.fees.max_fee_per_gas.to_string()),
+                max_priority_fee_per_gas_wei: Some(endpoint.fees.max_priority_fee_per_gas.to_string()),
+                confirm_strategy: (method == Method::Async).then(|| endpoint.confirmer.strategy().clone()),
+                concurrency: None,
+                total_duration_ms: duration_ms(elapsed),
+                interim: false,
+                failed_txs: num_transactions - transactions.len() as u64,
+                transactions,
+            },
+        });
+    }
+
+    let report = BenchReport {
+        test_name: args.name.clone(),
+        timestamp: started_at,
+        runs,
+    };
+    report.print_ranking();
+
+    match report.save(Path::new(RESULTS_DIR), &args.format) {
+        Ok(paths) => {
+            for path in paths {
+                println!("Report saved to: {}", path.display());
+            }
+        }
+        Err(e) => println!("Failed to generate report: {:#}", e),
+    }
+
+    Ok(())
+}
+
+/// Resolves a target into its label and profile. An RPC URL or IPC socket
+/// path, optionally preceded by `KEY_ENV=`, is an endpoint; everything else
+/// a profile name, optionally preceded by `LABEL=`. The key variable and
+/// the label come first so an `=` in a URL's query string is never taken
+/// for them.
+fn resolve_target(target: &str, config: &Config) -> Result<(String, Profile)> {
+    let is_endpoint = |endpoint: &str| endpoint.contains("://") || is_ipc_path(endpoint);
+    let (key_env, url) = match target.split_once('=') {
+        Some((key_env, url)) if is_env_name(key_env) && is_endpoint(url) => (Some(key_env.to_string()), url),
+        _ if is_endpoint(target) => (None, target),
+        Some(("", _)) => bail!("target `{}` has an empty label", target),
+        Some((label, name)) => return Ok((label.to_string(), config.profile(Some(name))?)),
+        None => return Ok((target.to_string(), config.profile(Some(target))?)),
+    };
+
+    // Label URL targets by host and port, e.g. `rpc.example.com:8545`,
+    // leaving out any credentials, and IPC targets by their path
+    let label = url
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split(['/', '?']).next())
+        .and_then(|authority| authority.rsplit('@').next())
+        .filter(|authority| !authority.is_empty())
+        .unwrap_or(url)
+        .to_string();
+
+    Ok((label, Profile {
+        http_url: Some(url.to_string()),
+        key_env,
+        ..Profile::default()
+    }))
+}
+
+/// Whether `name` looks like an environment variable name
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [profiles.mega]
+            http_url = "https://mega.example"
+            key_env = "MEGA_KEY"
+
+            [profiles.rise]
+            http_url = "https://rise.example"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bare_profile_is_labelled_by_name() {
+        let (label, profile) = resolve_target("mega", &config()).unwrap();
+        assert_eq!(label, "mega");
+        assert_eq!(profile.http_url.as_deref(), Some("https://mega.example"));
+    }
+
+    #[test]
+    fn labelled_profile() {
+        let (label, profile) = resolve_target("fast=rise", &config()).unwrap();
+        assert_eq!(label, "fast");
+        assert_eq!(profile.http_url.as_deref(), Some("https://rise.example"));
+    }
+
+    #[test]
+    fn key_env_before_a_url() {
+        let url = "https://user:pw@rpc.example:8545/v2?apikey=a=b";
+        let (label, profile) = resolve_target(&format!("PRIVATE_KEY_2={}", url), &config()).unwrap();
+        assert_eq!(label, "rpc.example:8545");
+        assert_eq!(profile.key_env.as_deref(), Some("PRIVATE_KEY_2"));
+        assert_eq!(profile.http_url.as_deref(), Some(url));
+    }
+
+    #[test]
+    fn key_env_before_an_ipc_path() {
+        let (label, profile) = resolve_target("KEY_1=/var/lib/reth/reth.ipc", &config()).unwrap();
+        assert_eq!(label, "/var/lib/reth/reth.ipc");
+        assert_eq!(profile.key_env.as_deref(), Some("KEY_1"));
+    }
+
+    #[test]
+    fn bare_url_keeps_the_default_key() {
+        let (label, profile) = resolve_target("https://rpc.example/?apikey=abc", &config()).unwrap();
+        assert_eq!(label, "rpc.example");
+        assert_eq!(profile.key_env, None);
+    }
+
+    #[test]
+    fn rejects_unknown_profiles_and_empty_labels() {
+        let error = resolve_target("base", &config()).unwrap_err();
+        assert!(error.to_string().starts_with("unknown profile `base`"), "{}", error);
+        assert!(resolve_target("fast=base", &config()).is_err());
+        let error = resolve_target("=mega", &config()).unwrap_err();
+        assert_eq!(error.to_string(), "target `=mega` has an empty label");
+    }
+
+    #[test]
+    fn env_names() {
+        assert!(is_env_name("PRIVATE_KEY_1"));
+        assert!(!is_env_name(""));
+        assert!(!is_env_name("https://rpc.example/?apikey"));
+        assert!(!is_env_name("MY-KEY"));
+    }
+}
@@ -0,0 +1,535 @@
+use anyhow::Result;
+use clap::Args;
+use futures::future::{join_all, try_join_all};
+use ethers::providers::Middleware;
+use rust_web3_utils::{
+    batch::BatchSender,
+    gas::Fees,
+    inclusion::{self, Submitted},
+    load::{self, KneeThresholds, LoadProfile, OpenLoopStats, ScheduledTx, SendRecord, Stage, StageStats},
+    nonce::{fill_gaps, NonceManager, NonceStatus},
+    report::duration_ms,
+    stats,
+    tx::TxType,
+    Connection, Profile,
+};
+use std::time::{Duration, Instant};
+
+use super::{parse_duration, parse_error_rate, parse_latency_ms, parse_rate, print_senders, starting_nonces, tx_count, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct BurstArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Number of transactions to send, defaults to the profile's tx_count or 10
+    #[arg(long, short = 'c', value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "rate")]
+    pub count: Option<u64>,
+
+    /// Send pre-signed transactions at this many per second instead of all
+    /// at once (open loop), for --duration
+    #[arg(long, value_name = "TPS", value_parser = parse_rate, requires = "duration")]
+    pub rate: Option<f64>,
+
+    /// How long to send at --rate, e.g. 90s, 5m or 1h
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "rate")]
+    pub duration: Option<Duration>,
+
+    /// Raise the send rate through stages instead of sending at one rate
+    /// (open loop), measuring inclusion per stage to find the saturation point
+    #[arg(long, value_enum, conflicts_with_all = ["count", "rate"], requires = "peak_rate")]
+    pub load: Option<LoadProfile>,
+
+    /// Send rate of the first stage of a staged run
+    #[arg(long, value_name = "TPS", value_parser = parse_rate, default_value_t = 10.0)]
+    pub start_rate: f64,
+
+    /// Send rate of the last stage of a step or ramp run, or of the spike
+    #[arg(long, value_name = "TPS", value_parser = parse_rate, requires = "load")]
+    pub peak_rate: Option<f64>,
+
+    /// Number of stages of a step or ramp run
+    #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub stages: u32,
+
+    /// How long each stage of a staged run lasts
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "30s")]
+    pub stage_duration: Duration,
+
+    /// p99 inclusion latency in ms past which a stage counts as saturated
+    /// [default: twice the first stage's p99]
+    #[arg(long, value_name = "MS", value_parser = parse_latency_ms)]
+    pub max_p99: Option<f64>,
+
+    /// Error rate in percent (rejected or never included) past which a
+    /// stage counts as saturated
+    #[arg(long, value_name = "PCT", value_parser = parse_error_rate, default_value_t = 1.0)]
+    pub max_error_rate: f64,
+
+    /// How long to wait for transactions to be included after the last
+    /// send of a burst or an open-loop run
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "60s")]
+    pub inclusion_timeout: Duration,
+
+    /// Don't follow the transactions of a burst or a --rate run until they
+    /// are included, only time how fast the node accepted them. Staged runs
+    /// always follow them, as the knee depends on inclusion.
+    #[arg(long, conflicts_with = "load")]
+    pub no_inclusion: bool,
+
+    /// Send an all-at-once burst as JSON-RPC batch requests of this many
+    /// `eth_sendRawTransaction` calls each, instead of one request per
+    /// transaction (HTTP endpoints only)
+    #[arg(long, value_name = "K", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["load", "rate"])]
+    pub batch_size: Option<u64>,
+
+    /// Number of sender wallets to spread the burst over, each with its own
+    /// nonce stream [default: every private key, or MNEMONIC_COUNT wallets]
+    #[arg(long, short = 's', value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub senders: Option<u32>,
+
+    /// Only report nonce gaps left by failed sends instead of filling them
+    /// with replacement self transfers
+    #[arg(long)]
+    pub no_gap_fill: bool,
+}
+
+pub async fn run(args: BurstArgs, profile: &Profile) -> Result<()> {
+    let stages = match (args.load, args.peak_rate, args.rate, args.duration) {
+        (Some(load), Some(peak_rate), _, _) => {
+            Some(load::plan(load, args.start_rate, peak_rate, args.stages, args.stage_duration)?)
+        }
+        (_, _, Some(rate), Some(duration)) => Some(vec![Stage::constant(rate, duration)]),
+        _ => None,
+    };
+    let num_transactions = match &stages {
+        Some(stages) => load::total_tx_count(stages)?,
+        None => tx_count(args.count, profile),
+    };
+
+    // Setup one connection per sender wallet, never more than transactions
+    let mut conns = args.connect.connect_senders(profile, args.senders).await?;
+    conns.truncate(num_transactions as usize);
+
+    // Display info
+    print_senders(&conns);
+
+    // Make necessary RPC calls before the transaction loop
+    let starting_nonces = starting_nonces(&conns).await?;
+    if let [starting_nonce] = starting_nonces[..] {
+        println!("Starting nonce: {}", starting_nonce);
+    }
+    let mut managers = conns
+        .iter()
+        .zip(&starting_nonces)
+        .map(|(conn, nonce)| NonceManager::new(conn.address, *nonce))
+        .collect::<Vec<_>>();
+    let (_, fees) = args.connect.fees(&conns[0], profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    println!("Transaction type: {}", tx_type);
+
+    match &stages {
+        Some(stages) => send_open_loop(&args, &conns, &mut managers, stages, tx_type, &fees).await?,
+        None => {
+            let from_block = conns[0].client.get_block_number().await?.as_u64();
+            let submitted = match args.batch_size {
+                Some(batch_size) => {
+                    send_batched(&conns, &mut managers, num_transactions, batch_size as usize, tx_type, &fees).await?
+                }
+                None => send_burst(&conns, &mut managers, num_transactions, tx_type, &fees).await?,
+            };
+            if !args.no_inclusion && !submitted.is_empty() {
+                report_inclusion(&conns[0], from_block, &submitted, args.inclusion_timeout).await?;
+            }
+        }
+    }
+
+    check_nonces(&conns, &mut managers, args.no_gap_fill, tx_type, &fees).await
+}
+
+/// Signs every transaction up front, then fires them all at once with
+/// `eth_sendRawTransaction`, so only the RPC is timed. Returns the index
+/// and hash of every transaction the node accepted.
+async fn send_burst(
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    num_transactions: u64,
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<Vec<(u64, Submitted)>> {
+    let txs = sign_burst(conns, managers, num_transactions, tx_type, fees).await?;
+
+    // Now send all transactions in parallel without awaiting each one
+    println!("\nSubmitting all transactions in parallel...");
+    let futures = txs.iter().map(|tx| {
+        let client = &conns[tx.sender].client;
+        async move {
+            let sent = Instant::now();
+            let result = client.send_raw_transaction(tx.raw.clone()).await.map(|pending| pending.tx_hash());
+            (tx, sent, result)
+        }
+    });
+
+    // Execute all sends in parallel
+    let sending_start = Instant::now();
+    let results = join_all(futures).await;
+    let sending_duration = sending_start.elapsed();
+
+    // Process results
+    let mut sent_txs = Vec::with_capacity(txs.len());
+    for (tx, sent, result) in results {
+        match result {
+            Ok(hash) => {
+                println!("TX #{} (nonce: {}): hash {}", tx.index + 1, tx.nonce, hash);
+                sent_txs.push((tx.index, Submitted { hash, sent }));
+                managers[tx.sender].mark_accepted(tx.nonce);
+            },
+            Err(e) => {
+                println!("TX #{} (nonce: {}): error: {}", tx.index + 1, tx.nonce, e);
+                managers[tx.sender].mark_failed(tx.nonce);
+            }
+        }
+    }
+
+    // Print summary
+    println!("\n===== SUMMARY =====");
+    println!("Total time to send all transactions: {:?}", sending_duration);
+    println!("Transactions per second: {:.2}", num_transactions as f64 / sending_duration.as_secs_f64());
+    println!("Total transactions sent: {}", sent_txs.len());
+
+    Ok(sent_txs)
+}
+
+/// Signs every transaction of an all-at-once burst before anything is
+/// sent, spreading the signing over the CPU cores
+async fn sign_burst(
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    num_transactions: u64,
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<Vec<ScheduledTx>> {
+    println!("\nSigning {} transactions...", num_transactions);
+    let sign_start = Instant::now();
+    let offsets = vec![(Duration::ZERO, 0); num_transactions as usize];
+    let txs = load::presign(conns, managers, &offsets, tx_type, fees).await?;
+    let sign_duration = sign_start.elapsed();
+    println!("All transactions signed in {:?} ({:.2} tx/s)",
+             sign_duration, txs.len() as f64 / sign_duration.as_secs_f64());
+    Ok(txs)
+}
+
+/// Signs every transaction up front and sends them all at once, packed
+/// into JSON-RPC batches of `batch_size` raw transactions. Returns the
+/// index and hash of every transaction the node accepted.
+async fn send_batched(
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    num_transactions: u64,
+    batch_size: usize,
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<Vec<(u64, Submitted)>> {
+    let sender = BatchSender::new(&conns[0].rpc_url)?;
+    let txs = sign_burst(conns, managers, num_transactions, tx_type, fees).await?;
+
+    let batches = txs.chunks(batch_size).collect::<Vec<_>>();
+    println!("\nSubmitting {} transactions in {} batches of up to {}...", txs.len(), batches.len(), batch_size);
+    let futures = batches.iter().map(|batch| {
+        let sender = &sender;
+        async move {
+            let raws = batch.iter().map(|tx| tx.raw.clone()).collect::<Vec<_>>();
+            let sent = Instant::now();
+            let result = sender.send_raw_transactions(&raws).await;
+            (sent, sent.elapsed(), result)
+        }
+    });
+
+    let sending_start = Instant::now();
+    let results = join_all(futures).await;
+    let sending_duration = sending_start.elapsed();
+
+    let mut sent_txs = Vec::with_capacity(txs.len());
+    let mut rejected_batches = 0;
+    for (b, (batch, (sent, _, result))) in batches.iter().zip(&results).enumerate() {
+        match result {
+            Ok(entries) => {
+                for (tx, entry) in batch.iter().zip(entries) {
+                    match entry {
+                        Ok(hash) => {
+                            println!("TX #{} (nonce: {}): hash {}", tx.index + 1, tx.nonce, hash);
+                            sent_txs.push((tx.index, Submitted { hash: *hash, sent: *sent }));
+                            managers[tx.sender].mark_accepted(tx.nonce);
+                        }
+                        Err(e) => {
+                            println!("TX #{} (nonce: {}): error: {}", tx.index + 1, tx.nonce, e);
+                            managers[tx.sender].mark_failed(tx.nonce);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("Batch #{} ({} transactions): error: {:#}", b + 1, batch.len(), e);
+                rejected_batches += 1;
+                for tx in batch.iter() {
+                    managers[tx.sender].mark_failed(tx.nonce);
+                }
+            }
+        }
+    }
+
+    let round_trips = stats::LatencyStats::from_durations(results.iter().map(|(_, round_trip, _)| *round_trip));
+    stats::print_table("BATCH LATENCY (ms):", &[("Batch round trip", &round_trips)]);
+
+    let failed = txs.len() - sent_txs.len();
+    println!("\n===== SUMMARY =====");
+    println!("Batches: {} of up to {} transactions, {} rejected as a whole", batches.len(), batch_size, rejected_batches);
+    println!("Accepted: {} of {}, failed: {} ({:.1}%)",
+             sent_txs.len(), txs.len(), failed, failed as f64 / txs.len() as f64 * 100.0);
+    println!("All batches submitted in {:?} ({:.2} accepted tx/s)",
+             sending_duration, sent_txs.len() as f64 / sending_duration.as_secs_f64());
+
+    Ok(sent_txs)
+}
+
+/// Follows every accepted transaction of a burst until it is included or
+/// the timeout passes, and prints where and how fast they landed
+async fn report_inclusion(
+    conn: &Connection,
+    from_block: u64,
+    submitted: &[(u64, Submitted)],
+    timeout: Duration,
+) -> Result<()> {
+    println!("\nFollowing {} transactions until they are included (timeout {:?})...", submitted.len(), timeout);
+    let txs = submitted.iter().map(|(_, tx)| *tx).collect::<Vec<_>>();
+    let report = inclusion::track(&conn.client, from_block, &txs, timeout).await?;
+
+    println!("\nINCLUSION:");
+    for ((i, tx), inclusion) in submitted.iter().zip(&report.inclusions) {
+        match inclusion {
+            Some(inclusion) => println!("TX #{}: block {}, included in {:.2} ms",
+                                        i + 1, inclusion.block_number, duration_ms(inclusion.latency)),
+            None => println!("TX #{}: not included after {:?}, dropped ({:?})", i + 1, timeout, tx.hash),
+        }
+    }
+
+    if !report.blocks.is_empty() {
+        println!("\nBLOCKS:");
+        println!("{:<12} {:>12} {:>10} {:>10}", "BLOCK", "TIMESTAMP", "BURST TXS", "ALL TXS");
+        println!("{}", "-".repeat(47));
+        for block in &report.blocks {
+            println!("{:<12} {:>12} {:>10} {:>10}", block.number, block.timestamp, block.tracked, block.total);
+        }
+    }
+
+    let latency = report.latency();
+    if !latency.is_empty() {
+        stats::print_table("INCLUSION LATENCY (ms):", &[("Send to inclusion", &latency)]);
+    }
+
+    println!("\n===== INCLUSION SUMMARY =====");
+    println!("Included: {} of {}, dropped: {} ({:.1}%)",
+             report.included(), submitted.len(), report.dropped(),
+             report.dropped() as f64 / submitted.len() as f64 * 100.0);
+    if let Some(span) = report.span {
+        println!("Blocks: {} ({:.1} burst transactions per block)",
+                 report.blocks.len(), report.included() as f64 / report.blocks.len() as f64);
+        println!("On-chain TPS: {:.2} ({} included over {:?} from the first send to the last inclusion)",
+                 report.onchain_tps(), report.included(), span);
+    }
+
+    Ok(())
+}
+
+/// Pre-signs every transaction of the stages and sends each at its
+/// scheduled time without waiting for earlier sends to finish. Latency is
+/// measured from the scheduled send time, so a node that can't keep up
+/// shows it even when the sender itself falls behind. Every accepted
+/// transaction is followed until it is included (unless --no-inclusion),
+/// and staged runs report each stage and the knee.
+async fn send_open_loop(
+    args: &BurstArgs,
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    stages: &[Stage],
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<()> {
+    let offsets = load::schedule(stages)?;
+    let duration = stages.iter().map(|stage| stage.duration).sum::<Duration>();
+    let description = match args.load {
+        Some(load) => format!("a {} load of {} stages from {} to {} tx/s, {:?} each",
+                              load, stages.len(), args.start_rate, args.peak_rate.unwrap_or_default(), args.stage_duration),
+        None => format!("{} tx/s for {:?}", stages[0].from_rate, duration),
+    };
+
+    println!("\nPre-signing {} transactions for {}...", offsets.len(), description);
+    let sign_start = Instant::now();
+    let txs = load::presign(conns, managers, &offsets, tx_type, fees).await?;
+    println!("All transactions signed in {:?}", sign_start.elapsed());
+
+    println!("\nSending {}...", description);
+    let inclusion_timeout = (!args.no_inclusion).then_some(args.inclusion_timeout);
+    let clients = conns.iter().map(|conn| conn.client.clone()).collect::<Vec<_>>();
+    let records = load::run_open_loop(&clients, txs, inclusion_timeout).await?;
+
+    let mut accepted = 0;
+    for record in &records {
+        match &record.result {
+            Ok(_) => {
+                accepted += 1;
+                managers[record.sender].mark_accepted(record.nonce);
+            }
+            Err(e) => {
+                println!("TX #{} (nonce: {}): error: {}", record.index + 1, record.nonce, e);
+                managers[record.sender].mark_failed(record.nonce);
+            }
+        }
+    }
+    let failed = records.len() - accepted;
+    let elapsed = records.iter().map(|r| r.done).max().unwrap_or_default();
+    let last_send = records.iter().map(|r| r.sent).max().unwrap_or_default();
+    let target_rate = records.len() as f64 / duration.as_secs_f64();
+
+    println!("\n===== SUMMARY =====");
+    println!("Target: {}", description);
+    // Each send owns one slot of the schedule, the last one included
+    println!("Achieved send rate: {:.2} tx/s ({} sent over {:?})",
+             records.len() as f64 / (last_send.as_secs_f64() + 1.0 / target_rate), records.len(), last_send);
+    println!("Accepted: {} ({:.2} tx/s over {:?}), failed: {} ({:.1}%)",
+             accepted, accepted as f64 / elapsed.as_secs_f64(), elapsed,
+             failed, failed as f64 / records.len() as f64 * 100.0);
+
+    let stats = OpenLoopStats::from_records(&records);
+    if !stats.service_time.is_empty() {
+        stats::print_table("OPEN-LOOP LATENCY (ms):", &stats.rows());
+        if stats.start_lag.p99_ms() > 1000.0 / target_rate {
+            println!("\nSends fell behind schedule (p99 start lag {:.2} ms); corrected latency includes the wait",
+                     stats.start_lag.p99_ms());
+        }
+    }
+
+    let stage_stats = StageStats::from_records(stages, &records);
+    if args.load.is_none() {
+        if inclusion_timeout.is_some() {
+            print_open_loop_inclusion(&stage_stats[0], &records);
+        }
+    } else {
+        print_stages(&stage_stats);
+
+        let thresholds = KneeThresholds {
+            max_p99_ms: args.max_p99,
+            max_error_rate: args.max_error_rate,
+        };
+        match load::knee(&stage_stats, &thresholds) {
+            Some((i, reason)) => {
+                println!("\nKnee at stage {} ({:.1} tx/s): {}", i + 1, stage_stats[i].target_rate(), reason);
+                match i.checked_sub(1) {
+                    Some(last) => println!("Highest sustained stage: {} ({:.1} tx/s)", last + 1, stage_stats[last].target_rate()),
+                    None => println!("Already saturated at the first stage; lower --start-rate"),
+                }
+            }
+            None => println!("\nNo knee: every stage stayed within the thresholds, up to {:.1} tx/s",
+                             stage_stats.iter().map(StageStats::target_rate).fold(0.0, f64::max)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints how many accepted transactions of a constant-rate run were
+/// included, how fast, and the on-chain rate they were included at
+fn print_open_loop_inclusion(run: &StageStats, records: &[SendRecord]) {
+    if !run.inclusion.is_empty() {
+        stats::print_table("INCLUSION LATENCY (ms):", &[("Scheduled to inclusion", &run.inclusion)]);
+    }
+
+    println!("\n===== INCLUSION SUMMARY =====");
+    let included = run.accepted - run.dropped;
+    println!("Included: {} of {} accepted, dropped: {} ({:.1}%)",
+             included, run.accepted, run.dropped, run.dropped as f64 / run.accepted.max(1) as f64 * 100.0);
+    if let Some(last) = records.iter().filter_map(|record| record.included).max() {
+        println!("On-chain TPS: {:.2} ({} included over {:?} from the start to the last inclusion)",
+                 included as f64 / last.as_secs_f64(), included, last);
+    }
+}
+
+/// Prints one row per stage with its rates and inclusion latency
+fn print_stages(stages: &[StageStats]) {
+    println!("\nSTAGES:");
+    println!("{:<6} {:>11} {:>7} {:>10} {:>8} {:>8} {:>12} {:>12}",
+             "STAGE", "TARGET TPS", "SENT", "ACCEPTED", "ERRORS", "DROPPED", "P50 INCL ms", "P99 INCL ms");
+    println!("{}", "-".repeat(82));
+    for (i, stage) in stages.iter().enumerate() {
+        let (p50, p99) = if stage.inclusion.is_empty() {
+            ("n/a".to_string(), "n/a".to_string())
+        } else {
+            (format!("{:.2}", stage.inclusion.p50_ms()), format!("{:.2}", stage.inclusion.p99_ms()))
+        };
+        println!("{:<6} {:>11.1} {:>7} {:>9.1}% {:>7.1}% {:>8} {:>12} {:>12}",
+                 i + 1, stage.target_rate(), stage.sent, stage.acceptance_rate(), stage.error_rate(),
+                 stage.dropped, p50, p99);
+    }
+}
+
+/// Prints the per-sender results, fills the nonce gaps failed sends left
+/// and reports every account still stuck
+async fn check_nonces(
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    no_gap_fill: bool,
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<()> {
+    if conns.len() > 1 {
+        println!("\nPer sender:");
+        println!("{:<42} {:>6} {:>6}", "SENDER", "SENT", "FAILED");
+        println!("{}", "-".repeat(56));
+        for manager in managers.iter() {
+            println!("{:<42} {:>6} {:>6}", format!("{:?}", manager.address()), manager.accepted(), manager.failed());
+        }
+    }
+
+    // A failed send leaves a gap that every later nonce of that sender
+    // queues behind, so find the gaps and fill them
+    for (conn, manager) in conns.iter().zip(managers.iter_mut()) {
+        let gaps = manager.gaps();
+        if gaps.is_empty() {
+            continue;
+        }
+        println!("\n{:?}: nonce gaps at {:?}", manager.address(), gaps);
+        if no_gap_fill {
+            continue;
+        }
+
+        println!("Filling {} gaps with replacement self transfers...", gaps.len());
+        for (nonce, result) in fill_gaps(conn.client.clone(), manager, tx_type, fees).await? {
+            match result {
+                Ok(hash) => println!("Nonce {} filled: hash {}", nonce, hash),
+                Err(e) => println!("Nonce {} could not be filled: {}", nonce, e),
+            }
+        }
+    }
+
+    // Compare every sender's nonces with the node's view
+    let statuses = try_join_all(
+        conns
+            .iter()
+            .zip(managers.iter())
+            .map(|(conn, manager)| NonceStatus::fetch(&conn.client, manager)),
+    )
+    .await?;
+    let stuck = statuses.iter().filter(|status| status.is_stuck()).collect::<Vec<_>>();
+    if stuck.is_empty() {
+        println!("\nNo stuck nonces: every sender's pending nonce is where it should be");
+    } else {
+        println!("\nSTUCK ACCOUNTS ({}):", stuck.len());
+        for status in stuck {
+            println!("{:?}: node pending nonce {} (latest {}), expected {}; nonce {} is missing and every later one is stuck",
+                     status.address, status.pending, status.latest, status.expected, status.pending);
+        }
+        println!("A new burst starts at the node's latest nonce and fills the gap");
+    }
+
+    Ok(())
+}
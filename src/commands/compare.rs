@@ -0,0 +1,167 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use rust_web3_utils::{
+    compare::mann_whitney_u,
+    report::{RunReport, RESULTS_DIR},
+    stats::{LatencyStats, Metric, RunStats},
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::parse_alpha;
+
+/// Length of the `%Y-%m-%d-%H%M%S` timestamp suffix in report file names
+const TIMESTAMP_LEN: usize = 17;
+
+/// A row of the comparison table: label and the statistic it shows
+type StatRow = (&'static str, fn(&LatencyStats) -> f64);
+
+#[derive(Args, Debug)]
+pub struct CompareArgs {
+    /// Reports to compare: JSON report paths, or test names resolved to the
+    /// latest matching JSON report in the results directory. The first one
+    /// is the baseline.
+    #[arg(value_name = "REPORT", num_args = 2.., required = true)]
+    pub reports: Vec<String>,
+
+    /// Directory test names are looked up in
+    #[arg(long, value_name = "DIR", default_value = RESULTS_DIR)]
+    pub dir: PathBuf,
+
+    /// Timing used for the significance test
+    #[arg(long, value_enum, default_value_t = Metric::Total)]
+    pub metric: Metric,
+
+    /// Significance level for the test
+    #[arg(long, value_parser = parse_alpha, default_value_t = 0.05)]
+    pub alpha: f64,
+}
+
+pub fn run(args: CompareArgs) -> Result<()> {
+    let mut runs = Vec::with_capacity(args.reports.len());
+    for reference in &args.reports {
+        let path = resolve_report(reference, &args.dir)?;
+        let report = RunReport::load(&path)?;
+        if report.transactions.is_empty() {
+            bail!("{} contains no transactions", path.display());
+        }
+        runs.push((label(&report, &path), path, report));
+    }
+
+    println!("Comparing {} runs (baseline: {})", runs.len(), runs[0].0);
+    for (i, (label, path, report)) in runs.iter().enumerate() {
+        println!("  [{}] {}: {} txs, method {}, {} ({})",
+                 i + 1, label, report.transactions.len(), report.method, report.rpc_url, path.display());
+    }
+
+    let stats = runs.iter().map(|(_, _, report)| report.stats()).collect::<Vec<_>>();
+    for metric in [Metric::Send, Metric::Confirm, Metric::Total] {
+        print_metric_table(metric, &stats);
+    }
+
+    // Rank test of every run against the baseline
+    println!("\nSIGNIFICANCE ({}, Mann-Whitney U, alpha {}):", args.metric.name(), args.alpha);
+    let baseline = runs[0].2.samples(args.metric);
+    let baseline_p50 = stats[0].metric(args.metric).p50_ms();
+    for (i, (label, _, report)) in runs.iter().enumerate().skip(1) {
+        let samples = report.samples(args.metric);
+        let delta = stats[i].metric(args.metric).p50_ms() - baseline_p50;
+        match mann_whitney_u(&samples, &baseline) {
+            Some(test) => {
+                let verdict = if !test.is_significant(args.alpha) {
+                    "no significant difference".to_string()
+                } else if test.z < 0.0 {
+                    format!("{} is significantly faster", label)
+                } else {
+                    format!("{} is significantly slower", label)
+                };
+                println!("  [{}] vs [1]: p50 delta {:+.2} ms, U = {:.1}, z = {:.2}, p = {:.4} -> {}",
+                         i + 1, delta, test.u, test.z, test.p_value, verdict);
+            }
+            None => println!("  [{}] vs [1]: p50 delta {:+.2} ms, not enough distinct samples to test", i + 1, delta),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints one row per statistic with a column per run and the delta of
+/// every run against the baseline
+fn print_metric_table(metric: Metric, stats: &[RunStats]) {
+    let rows: [StatRow; 8] = [
+        ("MIN", LatencyStats::min_ms),
+        ("P50", LatencyStats::p50_ms),
+        ("P90", LatencyStats::p90_ms),
+        ("P99", LatencyStats::p99_ms),
+        ("P99.9", LatencyStats::p999_ms),
+        ("MAX", LatencyStats::max_ms),
+        ("AVG", LatencyStats::mean_ms),
+        ("STDDEV", LatencyStats::stddev_ms),
+    ];
+
+    println!("\n{} (ms):", metric.name().to_uppercase());
+    let mut header = format!("{:<8}", "");
+    for i in 0..stats.len() {
+        header.push_str(&format!(" {:>10}", format!("[{}]", i + 1)));
+    }
+    for i in 1..stats.len() {
+        header.push_str(&format!(" {:>18}", format!("[{}] vs [1]", i + 1)));
+    }
+    println!("{}", header);
+    println!("{}", "-".repeat(header.len()));
+
+    for (name, value) in rows {
+        let values = stats.iter().map(|s| value(s.metric(metric))).collect::<Vec<_>>();
+        let mut line = format!("{:<8}", name);
+        for v in &values {
+            line.push_str(&format!(" {:>10.2}", v));
+        }
+        for v in &values[1..] {
+            let delta = v - values[0];
+            let pct = if values[0] != 0.0 {
+                format!("{:+.1}%", delta / values[0] * 100.0)
+            } else {
+                "n/a".to_string()
+            };
+            line.push_str(&format!(" {:>18}", format!("{:+.2} ({})", delta, pct)));
+        }
+        println!("{}", line);
+    }
+}
+
+/// Treats `reference` as a path if it exists, otherwise as a test name and
+/// picks the newest `<name>-<timestamp>.json` in `dir`
+fn resolve_report(reference: &str, dir: &Path) -> Result<PathBuf> {
+    let path = Path::new(reference);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    let prefix = format!("{}-", reference);
+    let mut matches = fs::read_dir(dir)
+        .with_context(|| format!("{} is not a file and {} could not be read", reference, dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter(|p| {
+            p.file_stem()
+                .and_then(|s| s.to_str())
+                .is_some_and(|stem| stem.starts_with(&prefix) && stem.len() == prefix.len() + TIMESTAMP_LEN)
+        })
+        .collect::<Vec<_>>();
+
+    // Timestamps sort lexically, so the last one is the newest
+    matches.sort();
+    matches
+        .pop()
+        .with_context(|| format!("no JSON report named {} found in {}", reference, dir.display()))
+}
+
+fn label(report: &RunReport, path: &Path) -> String {
+    report
+        .test_name
+        .clone()
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_default()
+}
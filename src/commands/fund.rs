@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use ethers::{providers::Middleware, types::U256, utils::format_ether};
+use rust_web3_utils::{
+    transfer::{self, Transfer},
+    tx, Profile,
+};
+use std::time::Duration;
+
+use super::{balances, connect_master_and_senders, parse_duration, parse_ether, ConfirmArgs, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct FundArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub confirm: ConfirmArgs,
+
+    /// Balance in ETH every sender wallet is topped up to
+    #[arg(long, short = 'b', value_name = "ETH", value_parser = parse_ether)]
+    pub balance: U256,
+
+    /// Number of sender wallets to fund [default: every configured wallet
+    /// after the first, which is the master]
+    #[arg(long, short = 's', value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub senders: Option<u32>,
+
+    /// How long to wait for each transfer's receipt, e.g. 90s or 5m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "2m")]
+    pub timeout: Duration,
+}
+
+pub async fn run(args: FundArgs, profile: &Profile) -> Result<()> {
+    let (master, senders) = connect_master_and_senders(&args.connect, profile, args.senders).await?;
+
+    println!("RPC URL: {}", master.rpc_url);
+    println!("Chain ID: {}", master.chain_id);
+    println!("Master wallet: {:?}", master.address);
+    println!("Funding {} sender wallets up to {} ETH each", senders.len(), format_ether(args.balance));
+
+    // Only top up wallets below the target
+    let current = balances(&senders).await?;
+    let top_ups = senders
+        .iter()
+        .zip(&current)
+        .filter(|(_, balance)| **balance < args.balance)
+        .map(|(conn, balance)| (conn.address, args.balance - *balance))
+        .collect::<Vec<_>>();
+    if top_ups.is_empty() {
+        println!("All sender wallets already hold at least {} ETH", format_ether(args.balance));
+        return Ok(());
+    }
+
+    let (_, fees) = args.connect.fees(&master, profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    let confirmer = args.confirm.confirmer(&master, profile).await?;
+    let total_value = top_ups.iter().fold(U256::zero(), |sum, (_, value)| sum + *value);
+    let max_gas = tx::transfer_cost(tx_type, &fees) * top_ups.len();
+    let master_before = master.client.get_balance(master.address, None).await?;
+    if master_before < total_value + max_gas {
+        bail!("master wallet holds {} ETH but funding {} wallets needs {} ETH plus up to {} ETH gas",
+              format_ether(master_before), top_ups.len(), format_ether(total_value), format_ether(max_gas));
+    }
+    println!("Master balance: {} ETH", format_ether(master_before));
+    println!("Sending {} ETH to {} wallets ({} already funded)...",
+             format_ether(total_value), top_ups.len(), senders.len() - top_ups.len());
+
+    // Consecutive nonces from the master, all sent at once
+    let starting_nonce = master.client.get_transaction_count(master.address, None).await?.as_u64();
+    let transfers = top_ups
+        .into_iter()
+        .zip(starting_nonce..)
+        .map(|((to, value), nonce)| Transfer {
+            client: master.client.clone(),
+            from: master.address,
+            to,
+            value,
+            nonce,
+            tx_type,
+            fees,
+        })
+        .collect();
+    let results = transfer::execute(transfers, &confirmer, args.timeout).await;
+
+    transfer::print_summary(&results);
+    let master_after = master.client.get_balance(master.address, None).await?;
+    println!("Master balance: {} ETH -> {} ETH", format_ether(master_before), format_ether(master_after));
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use clap::Args;
+use rust_web3_utils::{latency::send_and_confirm_transaction, tx, Profile};
+use std::time::Instant;
+
+use super::{ConfirmArgs, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct LatencyArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub confirm: ConfirmArgs,
+
+    /// Name of the test, shown in the output
+    #[arg(long, short = 'n', value_name = "NAME")]
+    pub name: Option<String>,
+}
+
+pub async fn run(args: LatencyArgs, profile: &Profile) -> Result<()> {
+    // Setup connection
+    let conn = args.connect.connect(profile).await?;
+    let client = conn.client.clone();
+
+    // Display info
+    conn.print_info();
+
+    // Make necessary RPC calls before the transaction
+    let (_, fees) = args.connect.fees(&conn, profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    println!("Transaction type: {}", tx_type);
+    let confirmer = args.confirm.confirmer(&conn, profile).await?;
+    if let Some(test_name) = &args.name {
+        println!("Test name: {}", test_name);
+    }
+
+    println!("\nSending a single transaction and measuring latency...");
+
+    // Start timing total transaction time
+    let tx_start = Instant::now();
+
+    let tx = tx::self_transfer(tx_type, conn.address, None, &fees);
+    match send_and_confirm_transaction(client, tx, &confirmer, true).await {
+        Ok((receipt, send_time, confirm_time)) => {
+            let total_time = tx_start.elapsed();
+            println!("\n===== SUMMARY =====");
+            println!("TX hash: {}", receipt.transaction_hash);
+            println!("Transaction sent and confirmed in {:?} (send: {:?}, confirm: {:?})",
+                    total_time, send_time, confirm_time);
+        },
+        Err(e) => {
+            println!("Error: {}", e);
+        }
+    }
+
+    Ok(())
+}
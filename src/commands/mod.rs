@@ -0,0 +1,418 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use ethers::{providers::Middleware, types::U256};
+use futures::future::try_join_all;
+use rust_web3_utils::{
+    config::GasPolicy,
+    confirm::{ConfirmMode, ConfirmStrategy, Confirmer},
+    gas::{self, Fees, GasMode, GasStrategy},
+    transport::supports_subscriptions,
+    tx::TxType,
+    ClientBuilder, Connection, Profile,
+};
+use std::{path::PathBuf, time::Duration};
+
+pub mod bench;
+pub mod burst;
+pub mod compare;
+pub mod fund;
+pub mod latency;
+pub mod spam;
+pub mod sub;
+pub mod sweep;
+pub mod sync;
+pub mod unstick;
+
+/// Transaction type used when neither the flag nor the profile sets one.
+/// The sync methods always sent EIP-1559, so the async method does too.
+const DEFAULT_TX_TYPE: TxType = TxType::Eip1559;
+
+/// Transaction count used when neither the flag nor the profile sets one
+const DEFAULT_TX_COUNT: u64 = 10;
+
+/// Connection, wallet and gas flags shared by every transaction-sending command.
+/// Flags take precedence over the selected profile, which takes precedence
+/// over the environment.
+#[derive(Args, Debug, Clone)]
+pub struct ConnectArgs {
+    /// RPC endpoint: an HTTP or WebSocket URL, or an IPC socket path.
+    /// Defaults to the profile's http_url or RPC_PROVIDER
+    #[arg(long, short = 'e', value_name = "URL")]
+    pub endpoint: Option<String>,
+
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// Transaction type sent by every submission method, defaults to the
+    /// profile's tx_type or eip1559
+    #[arg(long, value_enum)]
+    pub tx_type: Option<TxType>,
+
+    /// Encrypted keystore file, or a directory of them (one per sender
+    /// wallet), used instead of plaintext private keys
+    #[arg(long, value_name = "PATH")]
+    pub keystore: Option<PathBuf>,
+
+    /// File holding the keystore password; prompted for when not given
+    #[arg(long, value_name = "PATH")]
+    pub password_file: Option<PathBuf>,
+}
+
+impl ConnectArgs {
+    /// Builds the signing client from the profile, with `--endpoint` on top
+    pub async fn connect(&self, profile: &Profile) -> Result<Connection> {
+        self.client_builder(profile)?.build().await
+    }
+
+    /// Builds one signing client per configured sender wallet, keeping the
+    /// first `senders` of them (or deriving that many from the mnemonic)
+    pub async fn connect_senders(&self, profile: &Profile, senders: Option<u32>) -> Result<Vec<Connection>> {
+        let mut builder = self.client_builder(profile)?;
+        if let Some(senders) = senders {
+            builder = builder.wallet_count(senders);
+        }
+        builder.build_all().await
+    }
+
+    fn client_builder(&self, profile: &Profile) -> Result<ClientBuilder> {
+        let mut builder = profile.client_builder()?;
+        if let Some(endpoint) = &self.endpoint {
+            builder = builder.rpc_url(endpoint);
+        }
+        if let Some(keystore) = &self.keystore {
+            builder = builder.keystore(keystore);
+        }
+        if let Some(password_file) = &self.password_file {
+            builder = builder.password_file(password_file);
+        }
+        Ok(builder)
+    }
+
+    /// Resolves the gas strategy against the node and prints the fees
+    pub async fn fees(&self, conn: &Connection, profile: &Profile) -> Result<(GasStrategy, Fees)> {
+        self.gas.fees(conn, profile).await
+    }
+
+    /// Resolves the transaction type from the flag, then the profile
+    pub fn tx_type(&self, profile: &Profile) -> TxType {
+        tx_type(self.tx_type, profile)
+    }
+}
+
+/// Gas strategy flags. Every flag left out falls back to the profile's
+/// `[gas]` table, then to the defaults.
+#[derive(Args, Debug, Clone, Default)]
+pub struct GasArgs {
+    /// Gas strategy [default: fixed with --max-fee, otherwise multiplier]
+    #[arg(long = "gas", value_name = "MODE", value_enum)]
+    pub mode: Option<GasMode>,
+
+    /// Multiplier applied to the node's reported gas price [default: 3]
+    #[arg(long, value_name = "X", value_parser = parse_multiplier)]
+    pub gas_multiplier: Option<f64>,
+
+    /// Max fee per gas of the fixed strategy, in gwei
+    #[arg(long, value_name = "GWEI", value_parser = parse_gwei)]
+    pub max_fee: Option<f64>,
+
+    /// Priority fee per gas of the fixed and multiplier strategies, in gwei [default: 1]
+    #[arg(long, value_name = "GWEI", value_parser = parse_gwei)]
+    pub priority_fee: Option<f64>,
+
+    /// Reward percentile the fee-history strategy bids [default: 50]
+    #[arg(long, value_name = "P", value_parser = parse_percentile)]
+    pub fee_percentile: Option<f64>,
+
+    /// Number of recent blocks the fee-history strategy looks at [default: 10]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..=1024))]
+    pub fee_blocks: Option<u64>,
+}
+
+impl GasArgs {
+    /// The flags on top of the profile's gas policy
+    pub fn strategy(&self, profile: &Profile) -> Result<GasStrategy> {
+        GasPolicy {
+            mode: self.mode,
+            multiplier: self.gas_multiplier,
+            max_fee_gwei: self.max_fee,
+            priority_fee_gwei: self.priority_fee,
+            percentile: self.fee_percentile,
+            blocks: self.fee_blocks,
+        }
+        .or(&profile.gas)
+        .strategy()
+    }
+
+    /// Resolves the gas strategy against the node and prints the fees
+    pub async fn fees(&self, conn: &Connection, profile: &Profile) -> Result<(GasStrategy, Fees)> {
+        let strategy = self.strategy(profile)?;
+        let fees = strategy.resolve(&conn.client).await?;
+        println!("Gas strategy: {}", strategy);
+        println!("Using {}", fees);
+        Ok((strategy, fees))
+    }
+}
+
+/// Confirmation flags of the commands that wait for receipts
+#[derive(Args, Debug, Clone)]
+pub struct ConfirmArgs {
+    /// How async sends are confirmed, defaults to the profile's confirm or poll
+    #[arg(long, value_name = "MODE", value_enum)]
+    pub confirm: Option<ConfirmMode>,
+
+    /// Receipt poll interval of the poll strategy, head poll interval of
+    /// block-receipts, and longest interval of adaptive polling
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "100ms")]
+    pub poll_interval: Duration,
+
+    /// WebSocket URL or IPC socket path the new-heads strategy subscribes
+    /// on, defaults to the profile's ws_url, or the RPC endpoint when it is
+    /// a WebSocket or IPC one
+    #[arg(long, value_name = "URL")]
+    pub ws_endpoint: Option<String>,
+}
+
+impl ConfirmArgs {
+    /// The flags on top of the profile's confirm mode
+    pub fn strategy(&self, profile: &Profile) -> ConfirmStrategy {
+        let mode = self.confirm.or(profile.confirm).unwrap_or(ConfirmMode::Poll);
+        ConfirmStrategy::new(mode, self.poll_interval)
+    }
+
+    /// Sets up the confirmation strategy for `conn` and prints it
+    pub async fn confirmer(&self, conn: &Connection, profile: &Profile) -> Result<Confirmer> {
+        let ws_url = self
+            .ws_endpoint
+            .as_deref()
+            .or(profile.ws_url.as_deref())
+            .or(supports_subscriptions(&conn.rpc_url).then_some(conn.rpc_url.as_str()));
+        let confirmer = Confirmer::connect(self.strategy(profile), ws_url).await?;
+        println!("Confirmation: {}", confirmer.strategy());
+        Ok(confirmer)
+    }
+}
+
+/// Prints the connection details, listing every sender wallet when there
+/// is more than one
+pub fn print_senders(conns: &[Connection]) {
+    let Some(first) = conns.first() else {
+        return;
+    };
+    if conns.len() == 1 {
+        first.print_info();
+        return;
+    }
+
+    println!("RPC URL: {}", first.rpc_url);
+    println!("Transport: {}", first.client.provider().as_ref());
+    println!("Chain ID: {}", first.chain_id);
+    println!("Sender wallets ({}):", conns.len());
+    for conn in conns {
+        println!("  {:?}", conn.address);
+    }
+}
+
+/// Fetches every sender's current nonce, in sender order
+pub async fn starting_nonces(conns: &[Connection]) -> Result<Vec<u64>> {
+    let nonces = try_join_all(
+        conns
+            .iter()
+            .map(|conn| conn.client.get_transaction_count(conn.address, None)),
+    )
+    .await?;
+    Ok(nonces.iter().map(|nonce| nonce.as_u64()).collect())
+}
+
+/// Splits `total` transactions as evenly as possible over `senders`
+/// wallets, giving the remainder to the first ones
+pub fn split_count(total: u64, senders: usize) -> Vec<u64> {
+    let senders = senders as u64;
+    (0..senders)
+        .map(|i| total / senders + u64::from(i < total % senders))
+        .collect()
+}
+
+/// Connects the master wallet and the sender wallets. The master is the
+/// first configured wallet (the first mnemonic wallet, keystore file or
+/// private key), and the senders are the wallets after it.
+pub async fn connect_master_and_senders(
+    connect: &ConnectArgs,
+    profile: &Profile,
+    senders: Option<u32>,
+) -> Result<(Connection, Vec<Connection>)> {
+    let mut conns = connect
+        .connect_senders(profile, senders.map(|senders| senders.saturating_add(1)))
+        .await?;
+    let master = conns.remove(0);
+    let senders = conns
+        .into_iter()
+        .filter(|conn| conn.address != master.address)
+        .collect::<Vec<_>>();
+    if senders.is_empty() {
+        bail!("no sender wallets besides the master; set MNEMONIC_COUNT above 1, more keystore files or PRIVATE_KEY_2 and up");
+    }
+    Ok((master, senders))
+}
+
+/// Fetches every wallet's balance, in order
+pub async fn balances(conns: &[Connection]) -> Result<Vec<U256>> {
+    Ok(try_join_all(conns.iter().map(|conn| conn.client.get_balance(conn.address, None))).await?)
+}
+
+/// Resolves the transaction type from the flag, then the profile
+pub fn tx_type(tx_type: Option<TxType>, profile: &Profile) -> TxType {
+    tx_type.or(profile.tx_type).unwrap_or(DEFAULT_TX_TYPE)
+}
+
+/// Resolves the transaction count from the flag, then the profile
+pub fn tx_count(count: Option<u64>, profile: &Profile) -> u64 {
+    count.or(profile.tx_count).unwrap_or(DEFAULT_TX_COUNT)
+}
+
+fn parse_ether(s: &str) -> Result<U256, String> {
+    ethers::utils::parse_ether(s).map_err(|_| format!("`{}` is not an ETH amount", s))
+}
+
+fn parse_gwei(s: &str) -> Result<f64, String> {
+    let gwei: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !gwei.is_finite() || gwei < 0.0 {
+        return Err(format!("fee must not be negative, got {}", gwei));
+    }
+    Ok(gwei)
+}
+
+fn parse_percentile(s: &str) -> Result<f64, String> {
+    let percentile: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !(0.0..=100.0).contains(&percentile) {
+        return Err(format!("percentile must be between 0 and 100, got {}", percentile));
+    }
+    Ok(percentile)
+}
+
+fn parse_latency_ms(s: &str) -> Result<f64, String> {
+    let ms: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !ms.is_finite() || ms < 0.0 {
+        return Err(format!("latency must not be negative, got {}", ms));
+    }
+    Ok(ms)
+}
+
+fn parse_error_rate(s: &str) -> Result<f64, String> {
+    let percent: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(format!("error rate must be between 0 and 100 percent, got {}", percent));
+    }
+    Ok(percent)
+}
+
+fn parse_alpha(s: &str) -> Result<f64, String> {
+    let alpha: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(format!("significance level must be between 0 and 1 exclusive, got {}", alpha));
+    }
+    Ok(alpha)
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !rate.is_finite() || rate <= 0.0 {
+        return Err(format!("rate must be greater than zero, got {}", rate));
+    }
+    Ok(rate)
+}
+
+/// Parses a duration like `500ms`, `90s`, `5m` or `1h`; a bare number is seconds
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: f64 = value.parse().map_err(|_| format!("`{}` is not a duration like 90s, 5m or 1h", s))?;
+    let secs = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("unknown unit `{}` in `{}`, use ms, s, m or h", unit, s)),
+    };
+    if !secs.is_finite() || secs <= 0.0 {
+        return Err(format!("duration must be greater than zero, got `{}`", s));
+    }
+    let duration = Duration::try_from_secs_f64(secs).map_err(|_| format!("duration `{}` is too long", s))?;
+    // Below a nanosecond rounds down to zero, which timers reject
+    if duration.is_zero() {
+        return Err(format!("duration must be greater than zero, got `{}`", s));
+    }
+    Ok(duration)
+}
+
+fn parse_multiplier(s: &str) -> Result<f64, String> {
+    let multiplier: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !multiplier.is_finite() || multiplier < gas::MIN_MULTIPLIER {
+        return Err(format!("gas multiplier must be at least {}, got {}", gas::MIN_MULTIPLIER, multiplier));
+    }
+    Ok(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_count_spreads_the_remainder_over_the_first_senders() {
+        assert_eq!(split_count(10, 3), vec![4, 3, 3]);
+        assert_eq!(split_count(11, 3), vec![4, 4, 3]);
+        assert_eq!(split_count(9, 3), vec![3, 3, 3]);
+        assert_eq!(split_count(7, 1), vec![7]);
+    }
+
+    #[test]
+    fn split_count_with_more_senders_than_transactions() {
+        assert_eq!(split_count(2, 5), vec![1, 1, 0, 0, 0]);
+        assert_eq!(split_count(0, 2), vec![0, 0]);
+        assert_eq!(split_count(1000, 7).iter().sum::<u64>(), 1000);
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        for input in ["0", "-5s", "5x", "", "s", "1.2.3s", "0.0000000001s"] {
+            assert!(parse_duration(input).is_err(), "{} was accepted", input);
+        }
+        let overflow = "99999999999999999999999h";
+        assert_eq!(parse_duration(overflow).unwrap_err(), format!("duration `{}` is too long", overflow));
+    }
+
+    #[test]
+    fn latency_thresholds_are_finite_and_not_negative() {
+        assert_eq!(parse_latency_ms("250.5"), Ok(250.5));
+        assert_eq!(parse_latency_ms("0"), Ok(0.0));
+        for input in ["-1", "inf", "NaN", "fast"] {
+            assert!(parse_latency_ms(input).is_err(), "{} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn error_rates_are_percentages() {
+        assert_eq!(parse_error_rate("0"), Ok(0.0));
+        assert_eq!(parse_error_rate("2.5"), Ok(2.5));
+        assert_eq!(parse_error_rate("100"), Ok(100.0));
+        for input in ["-0.1", "100.1", "NaN", "inf", "1%"] {
+            assert!(parse_error_rate(input).is_err(), "{} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn significance_levels_are_strictly_between_zero_and_one() {
+        assert_eq!(parse_alpha("0.05"), Ok(0.05));
+        assert_eq!(parse_alpha("0.999"), Ok(0.999));
+        for input in ["0", "1", "-0.05", "5", "NaN", "inf", "5%"] {
+            assert!(parse_alpha(input).is_err(), "{} was accepted", input);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Result;
+use chrono::Utc;
+use clap::Args;
+use futures::future::join_all;
+use rust_web3_utils::{
+    confirm::Confirmer,
+    report::{duration_ms, ReportFormat, RunReport, TxRecord, RESULTS_DIR},
+    runner::{run_closed_loop, run_sequential, ClosedLoopRun, SequentialRun},
+    Method, Profile,
+};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::time::{interval_at, MissedTickBehavior};
+
+use super::{parse_duration, print_senders, split_count, starting_nonces, tx_count, ConfirmArgs, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct SpamArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub confirm: ConfirmArgs,
+
+    /// Submission method, defaults to the profile's method or async
+    #[arg(long, short = 'm', value_enum)]
+    pub method: Option<Method>,
+
+    /// Number of transactions to send, defaults to the profile's tx_count or 10
+    #[arg(long, short = 'c', value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "duration")]
+    pub count: Option<u64>,
+
+    /// Keep sending until this much time has passed (e.g. 90s, 30m, 2h)
+    /// instead of sending a fixed count, for soak tests
+    #[arg(long, short = 'd', value_name = "DURATION", value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+
+    /// How often a --duration run overwrites its report with an interim
+    /// snapshot, so a crash or Ctrl-C doesn't lose the run
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "1m")]
+    pub snapshot_interval: Duration,
+
+    /// Number of sender wallets to spread the transactions over, each
+    /// running its own sequential pipeline [default: every private key, or MNEMONIC_COUNT wallets]
+    #[arg(long, short = 's', value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub senders: Option<u32>,
+
+    /// Closed-loop mode: run N workers over the senders, each keeping one
+    /// transaction in flight and sending the next as soon as it confirms,
+    /// to measure throughput at a fixed concurrency. Workers beyond the
+    /// number of senders share a sender's nonces.
+    #[arg(long, short = 'w', value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub workers: Option<u64>,
+
+    /// Name of the test, used in the report title and file name
+    #[arg(long, short = 'n', value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Report formats to write into results/, comma separated
+    #[arg(long, short = 'f', value_enum, value_delimiter = ',', default_value = "markdown,json")]
+    pub format: Vec<ReportFormat>,
+}
+
+pub async fn run(args: SpamArgs, profile: &Profile) -> Result<()> {
+    let method = args.method.or(profile.method).unwrap_or(Method::Async);
+    let test_name = args.name.as_deref().unwrap_or("");
+    // Duration-based runs send until the deadline, not a fixed count
+    let num_transactions = match args.duration {
+        Some(_) => u64::MAX,
+        None => tx_count(args.count, profile),
+    };
+
+    // Setup one connection per sender wallet, never more than transactions
+    let mut conns = args.connect.connect_senders(profile, args.senders).await?;
+    conns.truncate(num_transactions.min(conns.len() as u64) as usize);
+    let conn = &conns[0];
+
+    // Display info
+    print_senders(&conns);
+
+    // Make necessary RPC calls before the transaction loop
+    let starting_nonces = starting_nonces(&conns).await?;
+    if let [starting_nonce] = starting_nonces[..] {
+        println!("Starting nonce: {}", starting_nonce);
+    }
+    let (gas_strategy, fees) = args.connect.fees(conn, profile).await?;
+    // Display test name and transaction method
+    if !test_name.is_empty() {
+        println!("Test name: {}", test_name);
+    }
+    println!("Transaction method: {}", method);
+    let tx_type = args.connect.tx_type(profile);
+    println!("Transaction type: {}", tx_type);
+    // Only async sends wait for receipts; the sync methods return them
+    let confirmer = match method {
+        Method::Async => args.confirm.confirmer(conn, profile).await?,
+        _ => Confirmer::default(),
+    };
+
+    // Start timer for entire batch
+    let started_at = Utc::now();
+    let batch_start_time = Instant::now();
+
+    let concurrency = match (args.workers, args.duration) {
+        (Some(workers), _) => Some(workers.min(num_transactions)),
+        // Duration-based runs are closed loop, one worker per sender by default
+        (None, Some(_)) => Some(conns.len() as u64),
+        (None, None) => None,
+    };
+    let report_of = |mut transactions: Vec<TxRecord>, failed_txs: u64, elapsed: Duration, interim: bool| {
+        transactions.sort_by_key(|tx| tx.index);
+        RunReport {
+            test_name: args.name.clone(),
+            timestamp: started_at,
+            rpc_url: conn.rpc_url.clone(),
+            chain_id: conn.chain_id.as_u64(),
+            wallet: conn.address,
+            senders: conns.iter().map(|conn| conn.address).collect(),
+            method,
+            tx_type: Some(tx_type),
+            gas_price_wei: fees.gas_price.to_string(),
+            gas_strategy: Some(gas_strategy.clone()),
+            max_fee_per_gas_wei: Some(fees.max_fee_per_gas.to_string()),
+            max_priority_fee_per_gas_wei: Some(fees.max_priority_fee_per_gas.to_string()),
+            confirm_strategy: (method == Method::Async).then(|| confirmer.strategy().clone()),
+            concurrency,
+            total_duration_ms: duration_ms(elapsed),
+            interim,
+            failed_txs,
+            transactions,
+        }
+    };
+
+    let (results, failed) = match concurrency {
+        Some(workers) => {
+            match args.duration {
+                Some(duration) => println!("\nSending for {:?} with {} workers over {} sender(s), each keeping one transaction in flight...",
+                                           duration, workers, conns.len()),
+                None => println!("\nSending {} transactions with {} workers over {} sender(s), each keeping one transaction in flight...",
+                                 num_transactions, workers, conns.len()),
+            }
+            let run = ClosedLoopRun {
+                method,
+                count: num_transactions,
+                deadline: args.duration.map(|duration| batch_start_time + duration),
+                workers,
+                tx_type,
+                fees,
+                confirmer: confirmer.clone(),
+            };
+            let records = Mutex::new(Vec::new());
+            let failed = AtomicU64::new(0);
+            let closed_loop = run_closed_loop(&conns, &starting_nonces, &run, &records, &failed);
+            tokio::pin!(closed_loop);
+
+            // Snapshots overwrite the final report's files until it is written
+            let mut snapshots = interval_at((batch_start_time + args.snapshot_interval).into(), args.snapshot_interval);
+            snapshots.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let outcome = loop {
+                tokio::select! {
+                    outcome = &mut closed_loop => break outcome,
+                    _ = snapshots.tick(), if args.duration.is_some() => {
+                        let snapshot = report_of(records.lock().unwrap().clone(), failed.load(Ordering::Relaxed),
+                                                 batch_start_time.elapsed(), true);
+                        save_snapshot(&snapshot, &args.format);
+                    }
+                    _ = tokio::signal::ctrl_c() => {
+                        println!("\nInterrupted, reporting the transactions confirmed so far");
+                        break Ok(());
+                    }
+                }
+            };
+            if let Err(e) = outcome {
+                println!("\nRun aborted, reporting the transactions confirmed so far: {:#}", e);
+            }
+            let failed = failed.load(Ordering::Relaxed);
+            if failed > 0 {
+                println!("\n{} transactions failed: send errors, or not confirmed before the run ended", failed);
+            }
+            let results = std::mem::take(&mut *records.lock().unwrap());
+            (results, failed)
+        }
+        None => {
+            println!("\nSending {} transactions sequentially from {} sender(s), waiting for confirmation after each...",
+                     num_transactions, conns.len());
+
+            // Every sender runs its own sequential pipeline with its own nonces
+            let mut first_index = 0;
+            let mut runs = Vec::with_capacity(conns.len());
+            for (count, starting_nonce) in split_count(num_transactions, conns.len()).into_iter().zip(&starting_nonces) {
+                runs.push(SequentialRun {
+                    method,
+                    count,
+                    first_index,
+                    starting_nonce: *starting_nonce,
+                    tx_type,
+                    fees,
+                    confirmer: confirmer.clone(),
+                    verbose: conns.len() == 1,
+                });
+                first_index += count;
+            }
+            // A sender that stops early keeps what it confirmed, and
+            // doesn't take the other senders' results with it
+            let outcomes = join_all(conns.iter().zip(&runs).map(|(conn, run)| async move {
+                let mut records = Vec::with_capacity(run.count as usize);
+                let outcome = run_sequential(conn, run, &mut records).await;
+                (records, outcome)
+            }))
+            .await;
+            let mut results = Vec::with_capacity(num_transactions as usize);
+            for (conn, (records, outcome)) in conns.iter().zip(outcomes) {
+                results.extend(records);
+                if let Err(e) = outcome {
+                    println!("\nSender {:?} run aborted, reporting the transactions it confirmed: {:#}", conn.address, e);
+                }
+            }
+            // Skipped sends, and the ones an aborted sender never got to
+            let failed = num_transactions - results.len() as u64;
+            (results, failed)
+        }
+    };
+
+    let batch_elapsed = batch_start_time.elapsed();
+
+    // Print summary
+    println!("\n===== SUMMARY =====");
+    println!("Total time for all transactions: {:?}", batch_elapsed);
+    println!();
+
+    let report = report_of(results, failed, batch_elapsed, false);
+
+    println!("Individual Transaction Results:");
+    println!("{:<5} {:<12} {:<12} {:<12} {:<64}",
+             "TX#", "SEND (ms)", "CONFIRM (ms)", "TOTAL (ms)", "HASH");
+    println!("{}", "-".repeat(120));
+
+    for tx in &report.transactions {
+        println!("{:<5} {:<12.2} {:<12.2} {:<12.2} {:?}",
+                 tx.index + 1,
+                 tx.send_ms,
+                 tx.confirm_ms,
+                 tx.total_ms,
+                 tx.hash);
+    }
+
+    // Calculate percentiles and histograms
+    if !report.transactions.is_empty() {
+        let stats = report.stats();
+        stats.print();
+        report.print_sender_stats();
+
+        let mode = match report.concurrency {
+            Some(workers) => format!("with {} workers", workers),
+            None => "sequentially".to_string(),
+        };
+        println!("\nSUMMARY: {} transactions sent and confirmed {} in {} ms, {:.2} tx/s (p50: {:.2} ms, p99: {:.2} ms, max: {:.2} ms, avg: {:.2} ms)",
+            report.transactions.len(), mode, batch_elapsed.as_millis(), report.throughput(),
+            stats.total.p50_ms(), stats.total.p99_ms(), stats.total.max_ms(), stats.total.mean_ms());
+
+        // Write the report in every requested format
+        match report.save(Path::new(RESULTS_DIR), &args.format) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Report saved to: {}", path.display());
+                }
+            }
+            Err(e) => println!("Failed to generate report: {:#}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Overwrites the run's report files with an interim snapshot
+fn save_snapshot(report: &RunReport, formats: &[ReportFormat]) {
+    println!("[{:.0}s] {} transactions confirmed, {} failed, {:.2} tx/s",
+             report.total_duration_ms / 1000.0, report.transactions.len(), report.failed_txs, report.throughput());
+    if report.transactions.is_empty() && report.failed_txs == 0 {
+        return;
+    }
+    match report.save(Path::new(RESULTS_DIR), formats) {
+        Ok(paths) => {
+            let paths = paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+            println!("Snapshot saved to: {}", paths.join(", "));
+        }
+        Err(e) => println!("Failed to save snapshot: {:#}", e),
+    }
+}
@@ -2,11 +2,23 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use futures_util::{StreamExt, SinkExt};
 use serde_json::{json, Value};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use clap::Args;
+use rust_web3_utils::Profile;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let connect_addr = "wss://staging.riselabs.xyz/ws";
+#[derive(Args, Debug)]
+pub struct SubArgs {
+    /// WebSocket endpoint to subscribe to, defaults to the profile's ws_url
+    #[arg(long, short = 'e', value_name = "URL")]
+    pub endpoint: Option<String>,
+}
+
+pub async fn run(args: SubArgs, profile: &Profile) -> Result<()> {
+    let connect_addr = args
+        .endpoint
+        .as_deref()
+        .or(profile.ws_url.as_deref())
+        .ok_or_else(|| anyhow!("no WebSocket endpoint: pass --endpoint or set ws_url in the profile"))?;
     println!("Connecting to {}", connect_addr);
 
     let (ws_stream, _) = connect_async(connect_addr).await?;
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::Args;
+use ethers::{providers::Middleware, utils::format_ether};
+use rust_web3_utils::{
+    transfer::{self, Transfer},
+    tx, Profile,
+};
+use std::time::Duration;
+
+use super::{balances, connect_master_and_senders, parse_duration, starting_nonces, ConfirmArgs, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub confirm: ConfirmArgs,
+
+    /// Number of sender wallets to sweep [default: every configured wallet
+    /// after the first, which is the master]
+    #[arg(long, short = 's', value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub senders: Option<u32>,
+
+    /// How long to wait for each transfer's receipt, e.g. 90s or 5m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "2m")]
+    pub timeout: Duration,
+}
+
+pub async fn run(args: SweepArgs, profile: &Profile) -> Result<()> {
+    let (master, senders) = connect_master_and_senders(&args.connect, profile, args.senders).await?;
+
+    println!("RPC URL: {}", master.rpc_url);
+    println!("Chain ID: {}", master.chain_id);
+    println!("Master wallet: {:?}", master.address);
+    println!("Sweeping {} sender wallets back to the master", senders.len());
+
+    // Legacy and EIP-2930 transfers cost exactly gas limit times gas price,
+    // so sending the balance minus that leaves the wallet empty. EIP-1559
+    // ones reserve the max fee and leave the unused part behind.
+    let (_, fees) = args.connect.fees(&master, profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    let confirmer = args.confirm.confirmer(&master, profile).await?;
+    let fee = tx::transfer_cost(tx_type, &fees);
+
+    let current = balances(&senders).await?;
+    let nonces = starting_nonces(&senders).await?;
+    let mut transfers = Vec::new();
+    for ((conn, balance), nonce) in senders.iter().zip(current).zip(nonces) {
+        if balance <= fee {
+            println!("Skipping {:?}: balance {} ETH does not cover the {} ETH fee",
+                     conn.address, format_ether(balance), format_ether(fee));
+            continue;
+        }
+        transfers.push(Transfer {
+            client: conn.client.clone(),
+            from: conn.address,
+            to: master.address,
+            value: balance - fee,
+            nonce,
+            tx_type,
+            fees,
+        });
+    }
+    if transfers.is_empty() {
+        println!("Nothing to sweep");
+        return Ok(());
+    }
+
+    let master_before = master.client.get_balance(master.address, None).await?;
+    println!("Sweeping {} wallets...", transfers.len());
+    let results = transfer::execute(transfers, &confirmer, args.timeout).await;
+
+    transfer::print_summary(&results);
+    let master_after = master.client.get_balance(master.address, None).await?;
+    println!("Master balance: {} ETH -> {} ETH", format_ether(master_before), format_ether(master_after));
+
+    Ok(())
+}
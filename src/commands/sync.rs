@@ -0,0 +1,80 @@
+use anyhow::Result;
+use clap::{builder::PossibleValuesParser, builder::TypedValueParser, Args, ValueEnum};
+use ethers::providers::Middleware;
+use rust_web3_utils::{latency::send_raw_sync, receipt, tx, Method, Profile};
+use std::time::Instant;
+
+use super::ConnectArgs;
+
+#[derive(Args, Debug)]
+pub struct SyncArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Sync submission method, defaults to the profile's method if it is a
+    /// sync one, otherwise rise
+    #[arg(
+        long,
+        short = 'm',
+        value_parser = PossibleValuesParser::new(["rise", "mega"])
+            .map(|s| Method::from_str(&s, true).expect("restricted to valid methods")),
+    )]
+    pub method: Option<Method>,
+
+    /// Name of the test, shown in the output
+    #[arg(long, short = 'n', value_name = "NAME")]
+    pub name: Option<String>,
+}
+
+pub async fn run(args: SyncArgs, profile: &Profile) -> Result<()> {
+    let method = args
+        .method
+        .or(profile.method.filter(Method::is_sync))
+        .unwrap_or(Method::Rise);
+
+    // Setup connection
+    let conn = args.connect.connect(profile).await?;
+    let client = conn.client.clone();
+
+    // Display info
+    conn.print_info();
+
+    // Make necessary RPC calls before the transaction
+    let (_, fees) = args.connect.fees(&conn, profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    println!("Transaction type: {}", tx_type);
+    if let Some(test_name) = &args.name {
+        println!("Test name: {}", test_name);
+    }
+
+    println!("\nSending a single transaction using {}...", method.rpc_method());
+
+    // Get the nonce
+    let nonce = client.get_transaction_count(conn.address, None).await?.as_u64();
+
+    // Create and sign the transaction
+    let tx = tx::signable_self_transfer(tx_type, conn.address, conn.chain_id.as_u64(), nonce, &fees);
+    let raw_tx = tx::sign_raw(&client, &tx).await?;
+
+    let start = Instant::now();
+
+    // Send the raw transaction using the selected method
+    let receipt = send_raw_sync(client.clone(), method, raw_tx).await?;
+
+    // Measure transaction time
+    let tx_duration = start.elapsed();
+    println!("TX sent and confirmed in {:?}", tx_duration);
+
+    // Get the actual transaction hash from the receipt
+    let tx_hash = receipt.transaction_hash;
+
+    receipt::print_receipt(&receipt);
+    receipt::print_inclusion(&receipt);
+
+    println!("\n===== SUMMARY =====");
+    println!("TX hash: {}", tx_hash);
+    println!("Transaction sent and confirmed in a single call in {:?}", tx_duration);
+    println!("{} worked successfully!", method.rpc_method());
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use rust_web3_utils::{
+    nonce,
+    replace::{self, Replacement},
+    Profile,
+};
+use std::{collections::BTreeMap, time::Duration};
+
+use super::{parse_duration, print_senders, ConnectArgs};
+
+#[derive(Args, Debug)]
+pub struct UnstickArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Replace every stuck transaction with a zero-value self transfer
+    /// (cancel) or resend it with bumped fees (speed-up)
+    #[arg(long, value_enum, default_value_t = Replacement::Cancel)]
+    pub action: Replacement,
+
+    /// Number of sender wallets to check [default: every configured wallet]
+    #[arg(long, short = 's', value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub senders: Option<u32>,
+
+    /// Sends per nonce before giving up, bumping the fees by 12.5% each
+    /// time the node rejects the replacement as underpriced
+    #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub attempts: u32,
+
+    /// How long to wait for the replacements to be mined, e.g. 90s or 5m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "2m")]
+    pub timeout: Duration,
+}
+
+pub async fn run(args: UnstickArgs, profile: &Profile) -> Result<()> {
+    let conns = args.connect.connect_senders(profile, args.senders).await?;
+    print_senders(&conns);
+
+    let (_, fees) = args.connect.fees(&conns[0], profile).await?;
+    let tx_type = args.connect.tx_type(profile);
+    println!("Transaction type: {}", tx_type);
+    println!("Action: {}", args.action);
+
+    let mut blocked = 0;
+    let mut still_blocked = 0;
+    for conn in &conns {
+        let (latest, pending) = nonce::nonces(&conn.client, conn.address).await?;
+        if pending <= latest {
+            println!("\n{:?}: not blocked (nonce {})", conn.address, latest);
+            continue;
+        }
+        blocked += 1;
+        println!("\n{:?}: {} pending transactions at nonces {} to {}",
+                 conn.address, pending - latest, latest, pending - 1);
+
+        // The originals are needed to speed them up, and to outbid their
+        // fees when cancelling
+        let originals = match replace::mempool_transactions(&conn.client, conn.address).await {
+            Ok(originals) => originals,
+            Err(e) => {
+                println!("Could not read the mempool ({}), replacing at the configured fees", e);
+                BTreeMap::new()
+            }
+        };
+
+        for nonce in latest..pending {
+            let original = originals.get(&nonce);
+            if args.action == Replacement::SpeedUp && original.is_none() {
+                println!("Nonce {}: original not found in the mempool, cancelling instead", nonce);
+            }
+
+            let start_fees = replace::replacement_fees(&fees, original);
+            let sent = replace::send_replacement(conn.client.clone(), start_fees, args.attempts, |fees| {
+                replace::replacement_tx(args.action, tx_type, conn.address, nonce, original, fees)
+            })
+            .await;
+            match sent {
+                Ok((hash, fees)) => println!("Nonce {}: replaced by {:?} ({})", nonce, hash, fees),
+                // The original got mined while the others were being replaced
+                Err(e) if e.to_string().to_lowercase().contains("nonce too low") => {
+                    println!("Nonce {}: already mined", nonce)
+                }
+                Err(e) => println!("Nonce {}: replacement failed: {:#}", nonce, e),
+            }
+        }
+
+        match replace::wait_unblocked(&conn.client, conn.address, pending, args.timeout).await {
+            Ok((latest, _)) => println!("{:?} unblocked: latest nonce {}", conn.address, latest),
+            Err(e) => {
+                println!("{:#}", e);
+                still_blocked += 1;
+            }
+        }
+    }
+
+    println!();
+    if blocked == 0 {
+        println!("No stuck transactions: every account's pending nonce matches its latest nonce");
+    } else if still_blocked > 0 {
+        bail!("{} of {} blocked accounts are still blocked", still_blocked, blocked);
+    } else {
+        println!("All {} blocked accounts are unblocked", blocked);
+    }
+
+    Ok(())
+}
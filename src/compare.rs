@@ -0,0 +1,130 @@
+/// Result of a two-sided Mann-Whitney U test
+#[derive(Debug, Clone, Copy)]
+pub struct MannWhitney {
+    /// U statistic of the first sample
+    pub u: f64,
+    /// Normal approximation of U, negative when the first sample is faster
+    pub z: f64,
+    pub p_value: f64,
+}
+
+impl MannWhitney {
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// Two-sided Mann-Whitney U test using the normal approximation with tie
+/// correction. Latencies are rarely normally distributed, so a rank test is
+/// a better fit than a t-test. Returns `None` if either sample is empty or
+/// every value is tied.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let n1 = a.len() as f64;
+    let n2 = b.len() as f64;
+    let n = n1 + n2;
+
+    // Rank both samples together, giving tied values their average rank
+    let mut values: Vec<(f64, bool)> = a
+        .iter()
+        .map(|v| (*v, true))
+        .chain(b.iter().map(|v| (*v, false)))
+        .collect();
+    values.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < values.len() {
+        let mut j = i;
+        while j + 1 < values.len() && values[j + 1].0 == values[i].0 {
+            j += 1;
+        }
+        let tied = (j - i + 1) as f64;
+        let avg_rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum_a += values[i..=j].iter().filter(|(_, in_a)| *in_a).count() as f64 * avg_rank;
+        tie_term += tied * tied * tied - tied;
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let z = (u - mean) / variance.sqrt();
+    let p_value = (2.0 * (1.0 - normal_cdf(z.abs()))).clamp(0.0, 1.0);
+
+    Some(MannWhitney { u, z, p_value })
+}
+
+/// Standard normal CDF
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Abramowitz and Stegun 7.1.26, accurate to about 1.5e-7
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn separated_samples() {
+        let result = mann_whitney_u(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap();
+        assert_close(result.u, 0.0);
+        assert_close(result.z, -1.963961);
+        assert_close(result.p_value, 0.049535);
+        assert!(result.is_significant(0.05));
+        assert!(!result.is_significant(0.01));
+    }
+
+    #[test]
+    fn ties_get_average_ranks() {
+        let result = mann_whitney_u(&[1.0, 2.0, 2.0], &[2.0, 3.0, 4.0]).unwrap();
+        assert_close(result.u, 1.0);
+        assert_close(result.z, -1.623086);
+        assert_close(result.p_value, 0.104571);
+    }
+
+    #[test]
+    fn sample_order_flips_the_sign() {
+        let forward = mann_whitney_u(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap();
+        let backward = mann_whitney_u(&[4.0, 5.0, 6.0], &[1.0, 2.0, 3.0]).unwrap();
+        assert_close(backward.u, 9.0);
+        assert_close(backward.z, -forward.z);
+        assert_close(backward.p_value, forward.p_value);
+    }
+
+    #[test]
+    fn identical_samples_are_not_significant() {
+        let sample = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let result = mann_whitney_u(&sample, &sample).unwrap();
+        assert_close(result.u, 12.5);
+        assert_close(result.z, 0.0);
+        assert_close(result.p_value, 1.0);
+    }
+
+    #[test]
+    fn all_ties_or_empty_have_no_result() {
+        assert!(mann_whitney_u(&[7.0, 7.0, 7.0], &[7.0, 7.0]).is_none());
+        assert!(mann_whitney_u(&[], &[1.0]).is_none());
+        assert!(mann_whitney_u(&[1.0], &[]).is_none());
+    }
+}
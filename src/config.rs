@@ -0,0 +1,390 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    client::ClientBuilder,
+    confirm::ConfirmMode,
+    gas::{self, GasMode, GasStrategy},
+    method::Method,
+    tx::TxType,
+};
+
+/// Config file read when `--config` is not given
+pub const DEFAULT_CONFIG_PATH: &str = "web3-utils.toml";
+
+/// Named profiles loaded from the TOML config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when `--profile` is not given
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Endpoint, key and run defaults for one chain or provider.
+/// Every field is optional; anything missing falls back to the environment.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// JSON-RPC endpoint used for sending transactions, over HTTP,
+    /// WebSocket (`ws://`, `wss://`) or IPC (a socket path)
+    pub http_url: Option<String>,
+    /// WebSocket endpoint used for subscriptions
+    pub ws_url: Option<String>,
+    /// Name of the environment variable holding the private key,
+    /// so keys never have to live in the config file
+    pub key_env: Option<String>,
+    /// Names of the environment variables holding one key per sender
+    /// wallet, used instead of `key_env` for multi-wallet runs
+    pub key_envs: Option<Vec<String>>,
+    /// Encrypted Web3 Secret Storage keystore file, or a directory with one
+    /// keystore file per sender wallet, used instead of the keys
+    pub keystore: Option<PathBuf>,
+    /// File holding the keystore password; prompted for when not set
+    pub keystore_password_file: Option<PathBuf>,
+    /// Name of the environment variable holding a BIP-39 mnemonic the
+    /// sender wallets are derived from, taking precedence over the keys
+    pub mnemonic_env: Option<String>,
+    /// Base BIP-44 path for mnemonic wallets, wallet `i` being `<path>/i`
+    pub derivation_path: Option<String>,
+    /// Number of wallets derived from the mnemonic
+    pub mnemonic_count: Option<u32>,
+    #[serde(default)]
+    pub gas: GasPolicy,
+    /// Default submission method
+    pub method: Option<Method>,
+    /// Default transaction type
+    pub tx_type: Option<TxType>,
+    /// Default confirmation strategy of async sends
+    pub confirm: Option<ConfirmMode>,
+    /// Default number of transactions per run
+    pub tx_count: Option<u64>,
+}
+
+/// How the fees of a run are chosen. Unset fields fall back to the
+/// defaults in `gas`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GasPolicy {
+    /// Gas strategy, defaults to `fixed` when `max_fee_gwei` is set and
+    /// `multiplier` otherwise
+    pub mode: Option<GasMode>,
+    /// Multiplier applied to the node's gas price
+    pub multiplier: Option<f64>,
+    /// Max fee of the fixed strategy
+    pub max_fee_gwei: Option<f64>,
+    /// Priority fee of the fixed and multiplier strategies
+    pub priority_fee_gwei: Option<f64>,
+    /// Reward percentile of the fee-history strategy
+    pub percentile: Option<f64>,
+    /// Number of blocks the fee-history strategy looks at
+    pub blocks: Option<u64>,
+}
+
+impl GasPolicy {
+    /// Fills every unset field from `fallback`
+    pub fn or(self, fallback: &GasPolicy) -> GasPolicy {
+        GasPolicy {
+            mode: self.mode.or(fallback.mode),
+            multiplier: self.multiplier.or(fallback.multiplier),
+            max_fee_gwei: self.max_fee_gwei.or(fallback.max_fee_gwei),
+            priority_fee_gwei: self.priority_fee_gwei.or(fallback.priority_fee_gwei),
+            percentile: self.percentile.or(fallback.percentile),
+            blocks: self.blocks.or(fallback.blocks),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(multiplier) = self.multiplier {
+            if !multiplier.is_finite() || multiplier < gas::MIN_MULTIPLIER {
+                bail!("gas.multiplier must be at least {}, got {}", gas::MIN_MULTIPLIER, multiplier);
+            }
+        }
+        for (name, fee) in [("max_fee_gwei", self.max_fee_gwei), ("priority_fee_gwei", self.priority_fee_gwei)] {
+            if let Some(fee) = fee {
+                if !fee.is_finite() || fee < 0.0 {
+                    bail!("gas.{} must not be negative, got {}", name, fee);
+                }
+            }
+        }
+        if let Some(percentile) = self.percentile {
+            if !(0.0..=100.0).contains(&percentile) {
+                bail!("gas.percentile must be between 0 and 100, got {}", percentile);
+            }
+        }
+        if let Some(blocks) = self.blocks {
+            if !(1..=1024).contains(&blocks) {
+                bail!("gas.blocks must be between 1 and 1024, got {}", blocks);
+            }
+        }
+        if self.mode == Some(GasMode::Fixed) && self.max_fee_gwei.is_none() {
+            bail!("the fixed gas strategy needs a max fee (--max-fee or gas.max_fee_gwei)");
+        }
+        Ok(())
+    }
+
+    /// Validates the policy and turns it into a strategy
+    pub fn strategy(&self) -> Result<GasStrategy> {
+        self.validate()?;
+
+        let mode = self.mode.unwrap_or(if self.max_fee_gwei.is_some() {
+            GasMode::Fixed
+        } else {
+            GasMode::Multiplier
+        });
+        let priority_fee_gwei = self.priority_fee_gwei.unwrap_or(gas::DEFAULT_PRIORITY_FEE_GWEI);
+
+        Ok(match mode {
+            GasMode::Fixed => GasStrategy::Fixed {
+                max_fee_gwei: self.max_fee_gwei.context("the fixed gas strategy needs a max fee")?,
+                priority_fee_gwei,
+            },
+            GasMode::Multiplier => GasStrategy::Multiplier {
+                multiplier: self.multiplier.unwrap_or(gas::DEFAULT_MULTIPLIER),
+                priority_fee_gwei,
+            },
+            GasMode::FeeHistory => GasStrategy::FeeHistory {
+                percentile: self.percentile.unwrap_or(gas::DEFAULT_FEE_PERCENTILE),
+                blocks: self.blocks.unwrap_or(gas::DEFAULT_FEE_BLOCKS),
+            },
+            GasMode::Legacy => GasStrategy::Legacy,
+        })
+    }
+}
+
+impl Config {
+    /// Parses the config file at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let config: Config = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+
+        for (name, profile) in &config.profiles {
+            profile
+                .validate()
+                .with_context(|| format!("invalid profile `{}` in {}", name, path.display()))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Parses the config file if it exists, otherwise returns an empty config
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Looks up `name`, or the default profile when `name` is `None`.
+    /// Returns an empty profile when neither is set.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(Profile::default());
+        };
+
+        self.profiles.get(name).cloned().ok_or_else(|| {
+            let available = self.profiles.keys().cloned().collect::<Vec<_>>();
+            if available.is_empty() {
+                anyhow!("unknown profile `{}`: no profiles are defined", name)
+            } else {
+                anyhow!("unknown profile `{}`, available profiles: {}", name, available.join(", "))
+            }
+        })
+    }
+}
+
+impl Profile {
+    fn validate(&self) -> Result<()> {
+        self.gas.validate()?;
+        if self.tx_count == Some(0) {
+            bail!("tx_count must be at least 1");
+        }
+        if self.key_env.is_some() && self.key_envs.is_some() {
+            bail!("set either key_env or key_envs, not both");
+        }
+        if self.key_envs.as_ref().is_some_and(|envs| envs.is_empty()) {
+            bail!("key_envs must name at least one variable");
+        }
+        if self.mnemonic_count == Some(0) {
+            bail!("mnemonic_count must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Starts a `ClientBuilder` from the environment and applies the
+    /// profile's endpoint, key, keystore and mnemonic references on top
+    pub fn client_builder(&self) -> Result<ClientBuilder> {
+        let mut builder = ClientBuilder::from_env();
+        if let Some(http_url) = &self.http_url {
+            builder = builder.rpc_url(http_url);
+        }
+        if let Some(key_env) = &self.key_env {
+            let private_key = env::var(key_env)
+                .with_context(|| format!("{} (referenced by key_env) must be set", key_env))?;
+            builder = builder.private_key(private_key);
+        }
+        if let Some(key_envs) = &self.key_envs {
+            let private_keys = key_envs
+                .iter()
+                .map(|key_env| {
+                    env::var(key_env)
+                        .with_context(|| format!("{} (referenced by key_envs) must be set", key_env))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            builder = builder.private_keys(private_keys);
+        }
+        if let Some(keystore) = &self.keystore {
+            builder = builder.keystore(keystore);
+        }
+        if let Some(password_file) = &self.keystore_password_file {
+            builder = builder.password_file(password_file);
+        }
+        if let Some(mnemonic_env) = &self.mnemonic_env {
+            let phrase = env::var(mnemonic_env)
+                .with_context(|| format!("{} (referenced by mnemonic_env) must be set", mnemonic_env))?;
+            builder = builder.mnemonic(phrase);
+        }
+        if let Some(derivation_path) = &self.derivation_path {
+            builder = builder.derivation_path(derivation_path);
+        }
+        if let Some(mnemonic_count) = self.mnemonic_count {
+            builder = builder.mnemonic_count(mnemonic_count);
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml: &str) -> GasPolicy {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn profile(toml: &str) -> Profile {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn strategy_defaults_to_multiplier() {
+        assert_eq!(
+            GasPolicy::default().strategy().unwrap(),
+            GasStrategy::Multiplier {
+                multiplier: gas::DEFAULT_MULTIPLIER,
+                priority_fee_gwei: gas::DEFAULT_PRIORITY_FEE_GWEI,
+            }
+        );
+    }
+
+    #[test]
+    fn max_fee_alone_means_fixed() {
+        assert_eq!(
+            policy("max_fee_gwei = 50").strategy().unwrap(),
+            GasStrategy::Fixed { max_fee_gwei: 50.0, priority_fee_gwei: gas::DEFAULT_PRIORITY_FEE_GWEI }
+        );
+    }
+
+    #[test]
+    fn fee_history_fills_its_defaults() {
+        assert_eq!(
+            policy("mode = \"fee-history\"").strategy().unwrap(),
+            GasStrategy::FeeHistory { percentile: gas::DEFAULT_FEE_PERCENTILE, blocks: gas::DEFAULT_FEE_BLOCKS }
+        );
+    }
+
+    #[test]
+    fn unset_fields_fall_back() {
+        let merged = policy("multiplier = 2").or(&policy("multiplier = 5\npriority_fee_gwei = 3"));
+        assert_eq!(merged.multiplier, Some(2.0));
+        assert_eq!(merged.priority_fee_gwei, Some(3.0));
+    }
+
+    #[test]
+    fn rejects_invalid_policies() {
+        for toml in [
+            "multiplier = 0.0004",
+            "multiplier = -1",
+            "max_fee_gwei = -1",
+            "priority_fee_gwei = -0.5",
+            "percentile = 101",
+            "blocks = 0",
+            "blocks = 1025",
+            "mode = \"fixed\"",
+        ] {
+            assert!(policy(toml).strategy().is_err(), "{} was accepted", toml);
+        }
+        assert!(policy("multiplier = 0.001").strategy().is_ok());
+    }
+
+    #[test]
+    fn looks_up_the_named_or_default_profile() {
+        let config = config(
+            "default_profile = \"local\"\n\
+             [profiles.local]\nhttp_url = \"http://localhost:8545\"\n\
+             [profiles.testnet]\nhttp_url = \"https://testnet.example\"\n",
+        );
+        assert_eq!(config.profile(None).unwrap().http_url.as_deref(), Some("http://localhost:8545"));
+        assert_eq!(config.profile(Some("testnet")).unwrap().http_url.as_deref(), Some("https://testnet.example"));
+        assert!(Config::default().profile(None).unwrap().http_url.is_none());
+    }
+
+    #[test]
+    fn unknown_profiles_list_the_available_ones() {
+        let profiles = config("[profiles.local]\n[profiles.testnet]\n");
+        let error = profiles.profile(Some("mainnet")).unwrap_err().to_string();
+        assert_eq!(error, "unknown profile `mainnet`, available profiles: local, testnet");
+
+        let error = Config::default().profile(Some("mainnet")).unwrap_err().to_string();
+        assert_eq!(error, "unknown profile `mainnet`: no profiles are defined");
+
+        // A default profile that doesn't exist is just as unknown
+        let error = config("default_profile = \"gone\"").profile(None).unwrap_err();
+        assert!(error.to_string().starts_with("unknown profile `gone`"));
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        for (toml, message) in [
+            ("key_env = \"KEY\"\nkey_envs = [\"KEY_A\"]", "set either key_env or key_envs, not both"),
+            ("key_envs = []", "key_envs must name at least one variable"),
+            ("mnemonic_count = 0", "mnemonic_count must be at least 1"),
+            ("tx_count = 0", "tx_count must be at least 1"),
+            ("[gas]\nmultiplier = 0", "gas.multiplier must be at least"),
+            ("[gas]\nmode = \"fixed\"", "the fixed gas strategy needs a max fee"),
+        ] {
+            let error = profile(toml).validate().unwrap_err().to_string();
+            assert!(error.starts_with(message), "{}: {}", toml, error);
+        }
+        assert!(profile("key_envs = [\"KEY_A\", \"KEY_B\"]\nmnemonic_count = 1\ntx_count = 1").validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(toml::from_str::<Profile>("http_uri = \"http://localhost:8545\"").is_err());
+        assert!(toml::from_str::<GasPolicy>("max_fee = 1").is_err());
+    }
+
+    #[test]
+    fn load_names_the_invalid_profile() {
+        let path = env::temp_dir().join(format!("web3-utils-config-test-{}.toml", std::process::id()));
+        fs::write(&path, "[profiles.broken]\ntx_count = 0\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(format!("{:#}", error).starts_with("invalid profile `broken` in "), "{:#}", error);
+        assert!(format!("{:#}", error).ends_with(": tx_count must be at least 1"), "{:#}", error);
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use ethers::{
+    providers::{Middleware, Provider, StreamExt},
+    types::{TransactionReceipt, H256},
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time::sleep,
+};
+
+use crate::{
+    client::Client,
+    transport::{supports_subscriptions, Transport},
+};
+
+/// Receipt poll interval used when nothing else is configured
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// First interval of adaptive polling, doubled after every miss
+const ADAPTIVE_START_INTERVAL: Duration = Duration::from_millis(5);
+
+/// New heads buffered per waiter; a waiter further behind just checks its
+/// receipt once for the heads it missed
+const HEADS_CAPACITY: usize = 64;
+
+/// How sent transactions are confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmMode {
+    /// `eth_getTransactionReceipt` at a fixed interval
+    Poll,
+    /// `eth_getTransactionReceipt` at an interval doubling from 5 ms up to
+    /// the poll interval
+    Adaptive,
+    /// `eth_subscribe("newHeads")` over WebSocket or IPC, checking the
+    /// receipt on every new block
+    NewHeads,
+    /// `eth_getBlockReceipts` on every new block, polling the block number
+    BlockReceipts,
+}
+
+/// A confirmation strategy with its parameters, as recorded in reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum ConfirmStrategy {
+    Poll { interval_ms: f64 },
+    Adaptive { max_interval_ms: f64 },
+    NewHeads,
+    BlockReceipts { interval_ms: f64 },
+}
+
+impl ConfirmStrategy {
+    /// The strategy of `mode`, polling at `interval` where it polls
+    pub fn new(mode: ConfirmMode, interval: Duration) -> Self {
+        let interval_ms = interval.as_secs_f64() * 1000.0;
+        match mode {
+            ConfirmMode::Poll => ConfirmStrategy::Poll { interval_ms },
+            ConfirmMode::Adaptive => ConfirmStrategy::Adaptive { max_interval_ms: interval_ms },
+            ConfirmMode::NewHeads => ConfirmStrategy::NewHeads,
+            ConfirmMode::BlockReceipts => ConfirmStrategy::BlockReceipts { interval_ms },
+        }
+    }
+}
+
+impl Default for ConfirmStrategy {
+    fn default() -> Self {
+        ConfirmStrategy::new(ConfirmMode::Poll, DEFAULT_POLL_INTERVAL)
+    }
+}
+
+impl fmt::Display for ConfirmStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfirmStrategy::Poll { interval_ms } => write!(f, "poll (receipt every {} ms)", interval_ms),
+            ConfirmStrategy::Adaptive { max_interval_ms } => {
+                write!(f, "adaptive (receipt polled from every 5 ms up to every {} ms)", max_interval_ms)
+            }
+            ConfirmStrategy::NewHeads => write!(f, "new-heads (receipt checked on every newHeads block)"),
+            ConfirmStrategy::BlockReceipts { interval_ms } => {
+                write!(f, "block-receipts (eth_getBlockReceipts per block, head polled every {} ms)", interval_ms)
+            }
+        }
+    }
+}
+
+/// Waits for receipts with a strategy. The new-heads strategy subscribes
+/// once when connected and shares every head between the waiters, so no
+/// wait pays for a subscribe round trip.
+#[derive(Debug, Clone, Default)]
+pub struct Confirmer {
+    strategy: ConfirmStrategy,
+    heads: Option<Arc<broadcast::Receiver<u64>>>,
+}
+
+impl Confirmer {
+    /// Sets up `strategy`, connecting to `heads_endpoint` (a WebSocket URL
+    /// or IPC socket path) for new-heads
+    pub async fn connect(strategy: ConfirmStrategy, heads_endpoint: Option<&str>) -> Result<Self> {
+        let heads = match strategy {
+            ConfirmStrategy::NewHeads => {
+                let endpoint = heads_endpoint.filter(|endpoint| supports_subscriptions(endpoint)).ok_or_else(|| {
+                    anyhow!("the new-heads confirmation strategy needs a WebSocket or IPC endpoint (--ws-endpoint or ws_url in the profile)")
+                })?;
+                let heads = async { subscribe_heads(Provider::new(Transport::connect(endpoint).await?)).await }
+                    .await
+                    .with_context(|| format!("failed to subscribe on {}", endpoint))?;
+                Some(Arc::new(heads))
+            }
+            _ => None,
+        };
+        Ok(Self { strategy, heads })
+    }
+
+    pub fn strategy(&self) -> &ConfirmStrategy {
+        &self.strategy
+    }
+
+    /// Waits until `tx_hash` has a receipt
+    pub async fn wait(&self, client: &Client, tx_hash: H256) -> Result<TransactionReceipt> {
+        match self.strategy {
+            ConfirmStrategy::Poll { interval_ms } => {
+                let interval = Duration::from_secs_f64(interval_ms / 1000.0);
+                loop {
+                    if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+                        return Ok(receipt);
+                    }
+                    sleep(interval).await;
+                }
+            }
+            ConfirmStrategy::Adaptive { max_interval_ms } => {
+                // Fast chains confirm within the first few checks, slow
+                // ones back off instead of hammering the RPC
+                let max_interval = Duration::from_secs_f64(max_interval_ms / 1000.0);
+                let mut interval = ADAPTIVE_START_INTERVAL.min(max_interval);
+                loop {
+                    if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+                        return Ok(receipt);
+                    }
+                    sleep(interval).await;
+                    interval = (interval * 2).min(max_interval);
+                }
+            }
+            ConfirmStrategy::NewHeads => {
+                // Listen before the first check so no block is missed
+                // between them
+                let mut heads = self.heads.as_ref().expect("new-heads confirmer is connected").resubscribe();
+                if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+                    return Ok(receipt);
+                }
+                loop {
+                    match heads.recv().await {
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                            if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+                                return Ok(receipt);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err(anyhow!("newHeads subscription closed before {:?} was confirmed", tx_hash))
+                        }
+                    }
+                }
+            }
+            ConfirmStrategy::BlockReceipts { interval_ms } => {
+                let interval = Duration::from_secs_f64(interval_ms / 1000.0);
+                // Without a receipt now, the transaction is in a block after
+                // the current head
+                let mut next_block = client.get_block_number().await?.as_u64() + 1;
+                if let Some(receipt) = client.get_transaction_receipt(tx_hash).await? {
+                    return Ok(receipt);
+                }
+                loop {
+                    let head = client.get_block_number().await?.as_u64();
+                    while next_block <= head {
+                        let receipts = client.get_block_receipts(next_block).await?;
+                        if let Some(receipt) = receipts.into_iter().find(|r| r.transaction_hash == tx_hash) {
+                            return Ok(receipt);
+                        }
+                        next_block += 1;
+                    }
+                    sleep(interval).await;
+                }
+            }
+        }
+    }
+}
+
+/// Subscribes to newHeads over `provider` and forwards the number of every
+/// new block to the returned receiver and its resubscriptions, for as long
+/// as the subscription lasts
+async fn subscribe_heads(provider: Provider<Transport>) -> Result<broadcast::Receiver<u64>> {
+    let (heads, receiver) = broadcast::channel(HEADS_CAPACITY);
+    let (subscribed, subscription) = oneshot::channel();
+    tokio::spawn(async move {
+        let mut blocks = match provider.subscribe_blocks().await {
+            Ok(blocks) => {
+                let _ = subscribed.send(Ok(()));
+                blocks
+            }
+            Err(e) => {
+                let _ = subscribed.send(Err(e));
+                return;
+            }
+        };
+        while let Some(block) = blocks.next().await {
+            // Only fails while nobody is waiting
+            let _ = heads.send(block.number.map_or(0, |number| number.as_u64()));
+        }
+    });
+    subscription.await??;
+    Ok(receiver)
+}
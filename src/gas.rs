@@ -0,0 +1,289 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, Transaction, U256},
+    utils::{format_units, parse_units},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::client::Client;
+
+/// Multiplier applied to `eth_gasPrice` when nothing else is configured
+pub const DEFAULT_MULTIPLIER: f64 = 3.0;
+
+/// Smallest multiplier, as the gas price is scaled in thousandths
+pub const MIN_MULTIPLIER: f64 = 0.001;
+
+/// Priority fee used by the fixed and multiplier strategies when none is given
+pub const DEFAULT_PRIORITY_FEE_GWEI: f64 = 1.0;
+
+/// Reward percentile the fee-history strategy uses when none is given
+pub const DEFAULT_FEE_PERCENTILE: f64 = 50.0;
+
+/// Number of blocks the fee-history strategy looks at when none is given
+pub const DEFAULT_FEE_BLOCKS: u64 = 10;
+
+/// Gas price used when the node reports zero (1 gwei)
+const FALLBACK_GAS_PRICE: u64 = 1_000_000_000;
+
+/// How the fees of a run are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GasMode {
+    /// Fixed max fee and priority fee
+    Fixed,
+    /// `eth_gasPrice` times a multiplier
+    Multiplier,
+    /// EIP-1559 fees from a reward percentile of `eth_feeHistory`
+    FeeHistory,
+    /// `eth_gasPrice` as is, paid entirely as priority fee
+    Legacy,
+}
+
+/// A gas strategy with its parameters, as recorded in reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum GasStrategy {
+    Fixed { max_fee_gwei: f64, priority_fee_gwei: f64 },
+    Multiplier { multiplier: f64, priority_fee_gwei: f64 },
+    FeeHistory { percentile: f64, blocks: u64 },
+    Legacy,
+}
+
+/// Fees resolved by a strategy, ready to put on a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    /// Price used by legacy and EIP-2930 transactions
+    pub gas_price: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl GasStrategy {
+    /// Queries the node as the strategy requires and returns the fees
+    pub async fn resolve(&self, client: &Client) -> Result<Fees> {
+        match *self {
+            GasStrategy::Fixed { max_fee_gwei, priority_fee_gwei } => {
+                let max_fee = gwei(max_fee_gwei)?;
+                let priority_fee = gwei(priority_fee_gwei)?.min(max_fee);
+                Ok(Fees {
+                    gas_price: max_fee,
+                    max_fee_per_gas: max_fee,
+                    max_priority_fee_per_gas: priority_fee,
+                })
+            }
+            GasStrategy::Multiplier { multiplier, priority_fee_gwei } => {
+                let gas_price = client.get_gas_price().await?;
+                let gas_price = if gas_price.is_zero() {
+                    zero_gas_price_fallback()
+                } else {
+                    scale(gas_price, multiplier)
+                };
+                let priority_fee = gwei(priority_fee_gwei)?;
+
+                // Keep the max fee above the priority fee, at least 2x it
+                let max_fee = if gas_price > priority_fee { gas_price } else { priority_fee * 2 };
+                Ok(Fees {
+                    gas_price,
+                    max_fee_per_gas: max_fee,
+                    max_priority_fee_per_gas: priority_fee,
+                })
+            }
+            GasStrategy::FeeHistory { percentile, blocks } => {
+                let history = client
+                    .fee_history(blocks, BlockNumber::Latest, &[percentile])
+                    .await
+                    .context("eth_feeHistory failed; the node may not support EIP-1559")?;
+
+                // The last base fee is the one of the next block
+                let base_fee = *history
+                    .base_fee_per_gas
+                    .last()
+                    .ok_or_else(|| anyhow!("eth_feeHistory returned no base fees"))?;
+                let mut rewards = history
+                    .reward
+                    .iter()
+                    .filter_map(|block| block.first().copied())
+                    .collect::<Vec<_>>();
+                rewards.sort();
+                let priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+                // Twice the base fee survives six full blocks in a row
+                Ok(Fees {
+                    gas_price: base_fee + priority_fee,
+                    max_fee_per_gas: base_fee * 2 + priority_fee,
+                    max_priority_fee_per_gas: priority_fee,
+                })
+            }
+            GasStrategy::Legacy => {
+                let gas_price = client.get_gas_price().await?;
+                let gas_price = if gas_price.is_zero() { zero_gas_price_fallback() } else { gas_price };
+                Ok(Fees {
+                    gas_price,
+                    max_fee_per_gas: gas_price,
+                    max_priority_fee_per_gas: gas_price,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasStrategy::Fixed { max_fee_gwei, priority_fee_gwei } => {
+                write!(f, "fixed ({} gwei max fee, {} gwei priority fee)", max_fee_gwei, priority_fee_gwei)
+            }
+            GasStrategy::Multiplier { multiplier, priority_fee_gwei } => {
+                write!(f, "multiplier ({}x eth_gasPrice, {} gwei priority fee)", multiplier, priority_fee_gwei)
+            }
+            GasStrategy::FeeHistory { percentile, blocks } => {
+                write!(f, "fee-history (p{} reward over {} blocks)", percentile, blocks)
+            }
+            GasStrategy::Legacy => write!(f, "legacy (eth_gasPrice)"),
+        }
+    }
+}
+
+impl Fees {
+    /// Fees a sent transaction pays, with legacy prices standing in for
+    /// the EIP-1559 fees
+    pub fn paid_by(tx: &Transaction) -> Self {
+        let gas_price = tx.gas_price.or(tx.max_fee_per_gas).unwrap_or_default();
+        Self {
+            gas_price,
+            max_fee_per_gas: tx.max_fee_per_gas.unwrap_or(gas_price),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or(gas_price),
+        }
+    }
+
+    /// The higher of each fee
+    pub fn max(&self, other: &Fees) -> Self {
+        Self {
+            gas_price: self.gas_price.max(other.gas_price),
+            max_fee_per_gas: self.max_fee_per_gas.max(other.max_fee_per_gas),
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.max(other.max_priority_fee_per_gas),
+        }
+    }
+
+    /// Fees for a transaction replacing another at the same nonce. Nodes
+    /// require at least a 10% bump of both fees, so this bumps by 12.5%.
+    pub fn bumped(&self) -> Self {
+        let bump = |fee: U256| fee.saturating_mul(U256::from(1125)) / 1000 + 1;
+        Self {
+            gas_price: bump(self.gas_price),
+            max_fee_per_gas: bump(self.max_fee_per_gas),
+            max_priority_fee_per_gas: bump(self.max_priority_fee_per_gas),
+        }
+    }
+}
+
+impl fmt::Display for Fees {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gas price {} gwei, max fee {} gwei, priority fee {} gwei",
+            format_gwei(self.gas_price),
+            format_gwei(self.max_fee_per_gas),
+            format_gwei(self.max_priority_fee_per_gas)
+        )
+    }
+}
+
+/// `gas_price` times `multiplier`, scaled in thousandths so fractional
+/// multipliers like 1.5 work on U256
+fn scale(gas_price: U256, multiplier: f64) -> U256 {
+    gas_price.saturating_mul(U256::from((multiplier * 1000.0).round() as u64)) / 1000
+}
+
+/// Formats wei as gwei without trailing zeros, for any value
+pub fn format_gwei(wei: U256) -> String {
+    let gwei = format_units(wei, "gwei").unwrap_or_else(|_| format!("{} wei", wei));
+    if gwei.contains('.') {
+        gwei.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        gwei
+    }
+}
+
+/// Converts a gwei amount to wei
+pub fn gwei(amount: f64) -> Result<U256> {
+    if !amount.is_finite() || amount < 0.0 {
+        bail!("{} is not a valid gwei amount", amount);
+    }
+    Ok(parse_units(amount, "gwei")
+        .with_context(|| format!("{} is not a valid gwei amount", amount))?
+        .into())
+}
+
+/// Gas price used when the node reports zero
+fn zero_gas_price_fallback() -> U256 {
+    println!("Warning: RPC returned zero gas price, using 1 gwei as default");
+    U256::from(FALLBACK_GAS_PRICE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn formats_gwei_without_trailing_zeros() {
+        assert_eq!(format_gwei(U256::zero()), "0");
+        assert_eq!(format_gwei(U256::from(GWEI)), "1");
+        assert_eq!(format_gwei(U256::from(1_500_000_000u64)), "1.5");
+        assert_eq!(format_gwei(U256::from(1)), "0.000000001");
+        assert_eq!(format_gwei(U256::from(10 * GWEI)), "10");
+    }
+
+    #[test]
+    fn formats_the_largest_value_as_gwei() {
+        let formatted = format_gwei(U256::MAX);
+        assert!(!formatted.contains("wei"), "{}", formatted);
+        let (whole, fraction) = formatted.split_once('.').unwrap();
+        assert_eq!(format!("{}{}", whole, fraction), U256::MAX.to_string());
+    }
+
+    #[test]
+    fn scales_in_thousandths() {
+        let price = U256::from(10 * GWEI);
+        assert_eq!(scale(price, 1.0), price);
+        assert_eq!(scale(price, 1.5), U256::from(15 * GWEI));
+        assert_eq!(scale(price, 0.001), U256::from(10_000_000));
+        // Rounded to the nearest thousandth
+        assert_eq!(scale(price, 1.0004), price);
+        assert_eq!(scale(price, 1.0006), U256::from(10_010_000_000u64));
+        assert_eq!(scale(U256::from(999), 0.001), U256::zero());
+    }
+
+    #[test]
+    fn bumps_every_fee_by_more_than_ten_percent() {
+        let fees = Fees {
+            gas_price: U256::from(1000),
+            max_fee_per_gas: U256::from(7),
+            max_priority_fee_per_gas: U256::zero(),
+        };
+        let bumped = fees.bumped();
+        assert_eq!(bumped.gas_price, U256::from(1126));
+        // 7.875 rounds down, plus one
+        assert_eq!(bumped.max_fee_per_gas, U256::from(8));
+        assert_eq!(bumped.max_priority_fee_per_gas, U256::from(1));
+        for fee in [1u64, 9, 10, 11, 99, GWEI].map(U256::from) {
+            let bumped = Fees { gas_price: fee, max_fee_per_gas: fee, max_priority_fee_per_gas: fee }.bumped();
+            assert!(bumped.gas_price * 10 > fee * 11, "{} bumped to {}", fee, bumped.gas_price);
+        }
+        assert_eq!(Fees { gas_price: U256::MAX, ..fees }.bumped().gas_price, U256::MAX / 1000 + 1);
+    }
+
+    #[test]
+    fn converts_gwei_to_wei() {
+        assert_eq!(gwei(1.5).unwrap(), U256::from(1_500_000_000u64));
+        assert_eq!(gwei(0.0).unwrap(), U256::zero());
+        assert!(gwei(-1.0).is_err());
+        assert!(gwei(f64::NAN).is_err());
+        assert!(gwei(f64::INFINITY).is_err());
+    }
+}
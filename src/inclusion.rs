@@ -0,0 +1,242 @@
+use anyhow::Result;
+use ethers::{
+    providers::Middleware,
+    types::{Block, H256},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::mpsc::{error::TryRecvError, UnboundedReceiver},
+    time::sleep,
+};
+
+use crate::{client::Client, stats::LatencyStats};
+
+/// How often the chain head is polled while following transactions
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A transaction the node accepted, to follow until it is included
+#[derive(Debug, Clone, Copy)]
+pub struct Submitted {
+    pub hash: H256,
+    /// When its send started
+    pub sent: Instant,
+}
+
+/// Where a followed transaction landed and how long it took from its send
+#[derive(Debug, Clone, Copy)]
+pub struct Inclusion {
+    pub block_number: u64,
+    pub latency: Duration,
+}
+
+/// A block holding followed transactions
+#[derive(Debug, Clone)]
+pub struct BlockInclusion {
+    pub number: u64,
+    pub timestamp: u64,
+    /// Followed transactions in the block
+    pub tracked: usize,
+    /// Every transaction in the block
+    pub total: usize,
+}
+
+/// Outcome of following a set of transactions
+#[derive(Debug, Clone)]
+pub struct InclusionReport {
+    /// One entry per followed transaction in the order given, `None` for
+    /// the ones not included before the timeout (dropped)
+    pub inclusions: Vec<Option<Inclusion>>,
+    /// Blocks that included followed transactions, in order
+    pub blocks: Vec<BlockInclusion>,
+    /// From the first send to the last inclusion seen
+    pub span: Option<Duration>,
+}
+
+impl InclusionReport {
+    pub fn included(&self) -> usize {
+        self.inclusions.iter().flatten().count()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.inclusions.len() - self.included()
+    }
+
+    /// Send to inclusion latency of the included transactions
+    pub fn latency(&self) -> LatencyStats {
+        LatencyStats::from_durations(self.inclusions.iter().flatten().map(|inclusion| inclusion.latency))
+    }
+
+    /// Included transactions per second from the first send to the last
+    /// inclusion
+    pub fn onchain_tps(&self) -> f64 {
+        match self.span {
+            Some(span) if !span.is_zero() => self.included() as f64 / span.as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Reads the blocks after a starting block as the chain head passes them
+struct BlockReader {
+    next_block: u64,
+}
+
+impl BlockReader {
+    fn new(from_block: u64) -> Self {
+        Self { next_block: from_block + 1 }
+    }
+
+    /// Every block up to the current head not read yet, with its number
+    /// and when it was read
+    async fn read_new(&mut self, client: &Client) -> Result<Vec<(u64, Block<H256>, Instant)>> {
+        let head = client.get_block_number().await?.as_u64();
+        let mut blocks = Vec::new();
+        while self.next_block <= head {
+            // Nodes behind a load balancer may not have the head yet
+            let Some(block) = client.get_block(self.next_block).await? else {
+                break;
+            };
+            blocks.push((self.next_block, block, Instant::now()));
+            self.next_block += 1;
+        }
+        Ok(blocks)
+    }
+}
+
+/// Follows `txs` by reading every block after `from_block` as it appears
+/// until all of them are included or `timeout` passes. Reading whole
+/// blocks costs one call per block instead of one receipt poll per
+/// transaction, and also gives the block sizes. Inclusion is timed when
+/// the block is seen, so the latency includes up to one poll interval.
+pub async fn track(client: &Client, from_block: u64, txs: &[Submitted], timeout: Duration) -> Result<InclusionReport> {
+    let mut pending = txs.iter().enumerate().map(|(i, tx)| (tx.hash, i)).collect::<HashMap<_, _>>();
+    let mut inclusions = vec![None; txs.len()];
+    let mut blocks = Vec::new();
+    let mut last_seen = None;
+    let mut reader = BlockReader::new(from_block);
+    let start = Instant::now();
+
+    while !pending.is_empty() && start.elapsed() < timeout {
+        for (number, block, seen) in reader.read_new(client).await? {
+            let mut tracked = 0;
+            for hash in &block.transactions {
+                if let Some(i) = pending.remove(hash) {
+                    inclusions[i] = Some(Inclusion {
+                        block_number: number,
+                        latency: seen.duration_since(txs[i].sent),
+                    });
+                    tracked += 1;
+                }
+            }
+            if tracked > 0 {
+                blocks.push(BlockInclusion {
+                    number,
+                    timestamp: block.timestamp.as_u64(),
+                    tracked,
+                    total: block.transactions.len(),
+                });
+                last_seen = Some(seen);
+            }
+        }
+        if !pending.is_empty() {
+            sleep(BLOCK_POLL_INTERVAL).await;
+        }
+    }
+
+    let first_sent = txs.iter().map(|tx| tx.sent).min();
+    let span = first_sent.zip(last_seen).map(|(first, last)| last.duration_since(first));
+    Ok(InclusionReport { inclusions, blocks, span })
+}
+
+/// Follows transactions while they are still being sent: reads every block
+/// after `from_block` as it appears and records the block and the time each
+/// hash arriving on `hashes` was first seen in one, even when the block was
+/// read before the hash arrived. Stops once `hashes` is closed and every
+/// hash is included, or `timeout` after it closed. Hashes missing from the
+/// result were not included in time.
+pub async fn watch(
+    client: &Client,
+    from_block: u64,
+    mut hashes: UnboundedReceiver<H256>,
+    timeout: Duration,
+) -> Result<HashMap<H256, (u64, Instant)>> {
+    // Every transaction of the blocks read so far, since a hash can come
+    // back from its send after its block was already read
+    let mut seen = HashMap::new();
+    let mut watched = HashSet::new();
+    let mut pending = HashSet::new();
+    let mut closed_at = None;
+    let mut reader = BlockReader::new(from_block);
+
+    loop {
+        loop {
+            match hashes.try_recv() {
+                Ok(hash) => {
+                    watched.insert(hash);
+                    if !seen.contains_key(&hash) {
+                        pending.insert(hash);
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    closed_at.get_or_insert_with(Instant::now);
+                    break;
+                }
+            }
+        }
+
+        for (number, block, read_at) in reader.read_new(client).await? {
+            for hash in block.transactions {
+                pending.remove(&hash);
+                seen.insert(hash, (number, read_at));
+            }
+        }
+
+        if let Some(closed_at) = closed_at {
+            if pending.is_empty() || closed_at.elapsed() >= timeout {
+                break;
+            }
+        }
+        sleep(BLOCK_POLL_INTERVAL).await;
+    }
+
+    seen.retain(|hash, _| watched.contains(hash));
+    Ok(seen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn included(latency_ms: u64) -> Option<Inclusion> {
+        Some(Inclusion { block_number: 1, latency: Duration::from_millis(latency_ms) })
+    }
+
+    fn report(inclusions: Vec<Option<Inclusion>>, span: Option<Duration>) -> InclusionReport {
+        InclusionReport { inclusions, blocks: Vec::new(), span }
+    }
+
+    #[test]
+    fn counts_included_and_dropped() {
+        let report = report(vec![included(100), None, included(300), None], Some(Duration::from_secs(1)));
+        assert_eq!(report.included(), 2);
+        assert_eq!(report.dropped(), 2);
+        assert_eq!(report.latency().count(), 2);
+    }
+
+    #[test]
+    fn onchain_tps_is_included_over_the_span() {
+        let report = report(vec![included(100), included(200), included(300), None], Some(Duration::from_millis(1500)));
+        assert_eq!(report.onchain_tps(), 2.0);
+    }
+
+    #[test]
+    fn onchain_tps_without_a_span_is_zero() {
+        assert_eq!(report(vec![None, None], None).onchain_tps(), 0.0);
+        assert_eq!(report(vec![included(0)], Some(Duration::ZERO)).onchain_tps(), 0.0);
+        assert_eq!(report(Vec::new(), None).dropped(), 0);
+    }
+}
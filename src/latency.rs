@@ -0,0 +1,92 @@
+use anyhow::{bail, Result};
+use ethers::{
+    core::types::Bytes,
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, TransactionReceipt, H256},
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    client::Client,
+    confirm::Confirmer,
+    method::Method,
+    middleware::{
+        realtime_transaction::RealtimeTransactionMiddleware,
+        sync_transaction::SyncTransactionMiddleware,
+    },
+    receipt,
+};
+
+/// Submits a signed raw transaction with one of the sync methods, which
+/// return the receipt from the same call
+pub async fn send_raw_sync(client: Arc<Client>, method: Method, raw_tx: Bytes) -> Result<TransactionReceipt> {
+    let receipt = match method {
+        // Use eth_sendRawTransactionSync
+        Method::Rise => SyncTransactionMiddleware::new(client).send_raw_transaction_sync(raw_tx).await?,
+        // Use realtime_sendRawTransaction
+        Method::Mega => RealtimeTransactionMiddleware::new(client).send_raw_transaction_realtime(raw_tx).await?,
+        Method::Async => bail!("{} does not return a receipt, use send_and_confirm_transaction", method),
+    };
+    Ok(receipt)
+}
+
+/// Sends a transaction without waiting for confirmation or receipt
+pub async fn send_transaction(client: Arc<Client>, tx: TypedTransaction) -> Result<H256> {
+    // Start measuring send time
+    let send_start = Instant::now();
+
+    // Send transaction
+    let pending_tx = client.send_transaction(tx, None).await?;
+    let tx_hash = pending_tx.tx_hash();
+
+    // Measure send time
+    let send_duration = send_start.elapsed();
+    println!("TX sent in {:?}, hash: {}", send_duration, tx_hash);
+
+    Ok(tx_hash)
+}
+
+/// Sends a transaction and waits for the receipt with `confirmer`'s strategy.
+/// Returns the receipt together with the send and confirm durations. With
+/// `verbose` the hash, receipt and timings are printed as they come in.
+pub async fn send_and_confirm_transaction(
+    client: Arc<Client>,
+    tx: TypedTransaction,
+    confirmer: &Confirmer,
+    verbose: bool,
+) -> Result<(TransactionReceipt, Duration, Duration)> {
+    // Start measuring send time
+    let send_start = Instant::now();
+
+    // Send transaction
+    let pending_tx = client.send_transaction(tx, None).await?;
+    let tx_hash = pending_tx.tx_hash();
+
+    // Measure send time
+    let send_duration = send_start.elapsed();
+    if verbose {
+        println!("TX sent in {:?}, hash: {}", send_duration, tx_hash);
+    }
+
+    // Start measuring confirmation time
+    let confirm_start = Instant::now();
+
+    // Wait for receipt
+    if verbose {
+        println!("Waiting for confirmation...");
+    }
+    let receipt = confirmer.wait(&client, tx_hash).await?;
+
+    // Measure confirmation time
+    let confirm_duration = confirm_start.elapsed();
+    if verbose {
+        receipt::print_receipt(&receipt);
+        println!("TX confirmed in {:?}", confirm_duration);
+        receipt::print_inclusion(&receipt);
+    }
+
+    Ok((receipt, send_duration, confirm_duration))
+}
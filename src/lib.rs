@@ -0,0 +1,29 @@
+//! Shared building blocks for the RPC latency tools: client setup,
+//! transaction builders, send/confirm helpers, receipt printing and
+//! latency statistics.
+
+pub mod batch;
+pub mod client;
+pub mod compare;
+pub mod config;
+pub mod confirm;
+pub mod gas;
+pub mod inclusion;
+pub mod latency;
+pub mod load;
+pub mod method;
+pub mod middleware;
+pub mod nonce;
+pub mod receipt;
+pub mod replace;
+pub mod report;
+pub mod runner;
+pub mod stats;
+pub mod transfer;
+pub mod transport;
+pub mod tx;
+pub mod wallet;
+
+pub use client::{Client, ClientBuilder, Connection};
+pub use config::{Config, Profile};
+pub use method::Method;
@@ -0,0 +1,615 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use ethers::{
+    core::types::Bytes,
+    providers::Middleware,
+    types::H256,
+};
+use std::{fmt, num::NonZeroUsize, sync::Arc, thread, time::Duration};
+use tokio::{
+    sync::mpsc,
+    task,
+    time::{sleep_until, Instant},
+};
+
+use crate::{
+    client::{Client, Connection},
+    gas::Fees,
+    inclusion,
+    nonce::NonceManager,
+    stats::LatencyStats,
+    tx::{self, TxType},
+};
+
+/// Most transactions one rate-driven run may schedule. Every one of them is
+/// pre-signed and held in memory before the first send.
+pub const MAX_SCHEDULED_TXS: u64 = 1_000_000;
+
+/// How the send rate changes over a staged run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LoadProfile {
+    /// Constant rate per stage, rising in equal steps from the start to the peak rate
+    Step,
+    /// Rate rising linearly from the start to the peak rate, measured in equal stages
+    Ramp,
+    /// Start rate, then the peak rate, then the start rate again
+    Spike,
+}
+
+impl fmt::Display for LoadProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadProfile::Step => f.write_str("step"),
+            LoadProfile::Ramp => f.write_str("ramp"),
+            LoadProfile::Spike => f.write_str("spike"),
+        }
+    }
+}
+
+/// A stretch of a run whose send rate goes linearly from `from_rate` to
+/// `to_rate` (constant when they are equal)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stage {
+    pub start: Duration,
+    pub duration: Duration,
+    pub from_rate: f64,
+    pub to_rate: f64,
+}
+
+impl Stage {
+    /// A single stage at a constant rate
+    pub fn constant(rate: f64, duration: Duration) -> Self {
+        Self {
+            start: Duration::ZERO,
+            duration,
+            from_rate: rate,
+            to_rate: rate,
+        }
+    }
+
+    /// Number of transactions sent during the stage, at least one
+    pub fn tx_count(&self) -> u64 {
+        ((self.from_rate + self.to_rate) / 2.0 * self.duration.as_secs_f64()).ceil().max(1.0) as u64
+    }
+
+    /// Offset from the stage start of the `n`th transaction: the time the
+    /// rate integrated from the start reaches `n`
+    fn offset(&self, n: u64) -> Duration {
+        let n = n as f64;
+        let a = self.from_rate;
+        let k = (self.to_rate - a) / (2.0 * self.duration.as_secs_f64());
+        let secs = if k.abs() < f64::EPSILON {
+            n / a
+        } else {
+            (-a + (a * a + 4.0 * k * n).sqrt()) / (2.0 * k)
+        };
+        Duration::from_secs_f64(secs.max(0.0))
+    }
+}
+
+/// Splits a staged run into its stages. Spike runs always have three.
+/// Fails when the whole run doesn't fit in a `Duration`.
+pub fn plan(
+    profile: LoadProfile,
+    start_rate: f64,
+    peak_rate: f64,
+    stages: u32,
+    stage_duration: Duration,
+) -> Result<Vec<Stage>> {
+    let rates: Vec<(f64, f64)> = match profile {
+        LoadProfile::Step => (0..stages)
+            .map(|i| {
+                let rate = interpolate(start_rate, peak_rate, i, stages);
+                (rate, rate)
+            })
+            .collect(),
+        LoadProfile::Ramp => (0..stages)
+            .map(|i| {
+                let from = start_rate + (peak_rate - start_rate) * i as f64 / stages as f64;
+                let to = start_rate + (peak_rate - start_rate) * (i + 1) as f64 / stages as f64;
+                (from, to)
+            })
+            .collect(),
+        LoadProfile::Spike => vec![(start_rate, start_rate), (peak_rate, peak_rate), (start_rate, start_rate)],
+    };
+
+    // Every stage start is within the run, so only its end can overflow
+    if stage_duration.checked_mul(rates.len() as u32).is_none() {
+        bail!("{} stages of {:?} are too long a run", rates.len(), stage_duration);
+    }
+    Ok(rates
+        .into_iter()
+        .enumerate()
+        .map(|(i, (from_rate, to_rate))| Stage {
+            start: stage_duration * i as u32,
+            duration: stage_duration,
+            from_rate,
+            to_rate,
+        })
+        .collect())
+}
+
+/// Rate of step `i` of `steps`, the first at `from` and the last at `to`
+fn interpolate(from: f64, to: f64, i: u32, steps: u32) -> f64 {
+    if steps <= 1 {
+        return to;
+    }
+    from + (to - from) * i as f64 / (steps - 1) as f64
+}
+
+/// Number of transactions sent over all the stages. Fails past
+/// `MAX_SCHEDULED_TXS`, before anything is allocated for them.
+pub fn total_tx_count(stages: &[Stage]) -> Result<u64> {
+    stages
+        .iter()
+        .try_fold(0u64, |total, stage| total.checked_add(stage.tx_count()))
+        .filter(|total| *total <= MAX_SCHEDULED_TXS)
+        .ok_or_else(|| {
+            anyhow!("the run would send more than {} transactions; lower the rate or the duration", MAX_SCHEDULED_TXS)
+        })
+}
+
+/// Offsets of every transaction in the run, relative to its start, with
+/// the index of the stage each one belongs to
+pub fn schedule(stages: &[Stage]) -> Result<Vec<(Duration, usize)>> {
+    let mut offsets = Vec::with_capacity(total_tx_count(stages)? as usize);
+    for (i, stage) in stages.iter().enumerate() {
+        offsets.extend((0..stage.tx_count()).map(|n| (stage.start + stage.offset(n), i)));
+    }
+    Ok(offsets)
+}
+
+/// A signed transaction and when it is due, relative to the start of the run
+#[derive(Debug, Clone)]
+pub struct ScheduledTx {
+    pub index: u64,
+    pub sender: usize,
+    pub nonce: u64,
+    pub raw: Bytes,
+    pub scheduled: Duration,
+    pub stage: usize,
+}
+
+/// When a transaction was due, when it actually went out, when the node
+/// answered and when it was included, all relative to the start of the run
+#[derive(Debug)]
+pub struct SendRecord {
+    pub index: u64,
+    pub sender: usize,
+    pub nonce: u64,
+    pub stage: usize,
+    pub scheduled: Duration,
+    pub sent: Duration,
+    pub done: Duration,
+    pub result: Result<H256>,
+    /// When its block was seen, if inclusion was tracked and it was seen in time
+    pub included: Option<Duration>,
+    pub block_number: Option<u64>,
+}
+
+impl SendRecord {
+    /// How long the node took to answer
+    pub fn service_time(&self) -> Duration {
+        self.done.saturating_sub(self.sent)
+    }
+
+    /// Latency measured from when the transaction was due. When the sender
+    /// falls behind, the time spent waiting to send counts too, so overload
+    /// isn't hidden by the sender slowing down (coordinated omission).
+    pub fn corrected_latency(&self) -> Duration {
+        self.done.saturating_sub(self.scheduled)
+    }
+
+    /// How late the transaction went out
+    pub fn start_lag(&self) -> Duration {
+        self.sent.saturating_sub(self.scheduled)
+    }
+
+    /// Time from when the transaction was due until its receipt was seen
+    pub fn inclusion_latency(&self) -> Option<Duration> {
+        self.included.map(|included| included.saturating_sub(self.scheduled))
+    }
+}
+
+/// Builds one self transfer per offset, handing them to the senders round
+/// robin with consecutive nonces from each sender's manager, then signs
+/// them on blocking threads, one chunk per CPU core
+pub async fn presign(
+    conns: &[Connection],
+    managers: &mut [NonceManager],
+    offsets: &[(Duration, usize)],
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<Vec<ScheduledTx>> {
+    let mut unsigned = offsets
+        .iter()
+        .enumerate()
+        .map(|(i, (scheduled, stage))| {
+            let sender = i % conns.len();
+            let conn = &conns[sender];
+            let nonce = managers[sender].next_nonce();
+            let tx = tx::signable_self_transfer(tx_type, conn.address, conn.chain_id.as_u64(), nonce, fees);
+            let scheduled = ScheduledTx {
+                index: i as u64,
+                sender,
+                nonce,
+                raw: Bytes::default(),
+                scheduled: *scheduled,
+                stage: *stage,
+            };
+            (scheduled, tx)
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    let wallets = Arc::new(conns.iter().map(|conn| conn.client.signer().clone()).collect::<Vec<_>>());
+    let cores = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = offsets.len().div_ceil(cores).max(1);
+    let mut handles = Vec::with_capacity(cores);
+    loop {
+        let chunk = unsigned.by_ref().take(chunk_size).collect::<Vec<_>>();
+        if chunk.is_empty() {
+            break;
+        }
+        let wallets = wallets.clone();
+        handles.push(task::spawn_blocking(move || {
+            chunk
+                .into_iter()
+                .map(|(mut scheduled, tx)| {
+                    scheduled.raw = tx::sign_raw_with(&wallets[scheduled.sender], &tx)?;
+                    Ok(scheduled)
+                })
+                .collect::<Result<Vec<_>>>()
+        }));
+    }
+
+    let mut txs = Vec::with_capacity(offsets.len());
+    for handle in handles {
+        txs.extend(handle.await??);
+    }
+    Ok(txs)
+}
+
+/// Sends every transaction with `eth_sendRawTransaction` at its scheduled
+/// time, without waiting for earlier sends to finish (open loop). With an
+/// `inclusion_timeout`, every block is read while the sends go out to time
+/// when each accepted transaction is included, for up to that long after
+/// the last send. Reading blocks costs one call per block instead of one
+/// receipt poll per transaction in flight, which would load the node being
+/// measured. Returns the records in index order.
+pub async fn run_open_loop(
+    clients: &[Arc<Client>],
+    txs: Vec<ScheduledTx>,
+    inclusion_timeout: Option<Duration>,
+) -> Result<Vec<SendRecord>> {
+    let (hashes, watcher) = match inclusion_timeout {
+        Some(limit) => {
+            let client = clients[0].clone();
+            let from_block = client.get_block_number().await?.as_u64();
+            let (hashes, receiver) = mpsc::unbounded_channel();
+            let watcher = tokio::spawn(async move { inclusion::watch(&client, from_block, receiver, limit).await });
+            (Some(hashes), Some(watcher))
+        }
+        None => (None, None),
+    };
+
+    let start = Instant::now();
+    let mut handles = Vec::with_capacity(txs.len());
+    for tx in txs {
+        sleep_until(start + tx.scheduled).await;
+        let client = clients[tx.sender].clone();
+        let hashes = hashes.clone();
+        handles.push(tokio::spawn(async move {
+            let sent = start.elapsed();
+            let result = client
+                .send_raw_transaction(tx.raw)
+                .await
+                .map(|pending| pending.tx_hash())
+                .map_err(Into::into);
+            let done = start.elapsed();
+            if let (Ok(hash), Some(hashes)) = (&result, hashes) {
+                // Only fails once the watcher is gone, which ends the run anyway
+                let _ = hashes.send(*hash);
+            }
+
+            SendRecord {
+                index: tx.index,
+                sender: tx.sender,
+                nonce: tx.nonce,
+                stage: tx.stage,
+                scheduled: tx.scheduled,
+                sent,
+                done,
+                result,
+                included: None,
+                block_number: None,
+            }
+        }));
+    }
+    // The watcher stops once every sender of hashes is gone
+    drop(hashes);
+
+    let mut records = Vec::with_capacity(handles.len());
+    for handle in handles {
+        records.push(handle.await?);
+    }
+
+    if let Some(watcher) = watcher {
+        let included = watcher.await??;
+        for record in &mut records {
+            if let Some((block_number, seen)) = record.result.as_ref().ok().and_then(|hash| included.get(hash)) {
+                record.included = Some(seen.saturating_duration_since(start.into_std()));
+                record.block_number = Some(*block_number);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Service time, corrected latency and start lag of the accepted sends
+pub struct OpenLoopStats {
+    pub service_time: LatencyStats,
+    pub corrected_latency: LatencyStats,
+    pub start_lag: LatencyStats,
+}
+
+impl OpenLoopStats {
+    pub fn from_records(records: &[SendRecord]) -> Self {
+        let mut stats = Self {
+            service_time: LatencyStats::new(),
+            corrected_latency: LatencyStats::new(),
+            start_lag: LatencyStats::new(),
+        };
+        for record in records.iter().filter(|r| r.result.is_ok()) {
+            stats.service_time.record(record.service_time());
+            stats.corrected_latency.record(record.corrected_latency());
+            stats.start_lag.record(record.start_lag());
+        }
+        stats
+    }
+
+    pub fn rows(&self) -> [(&'static str, &LatencyStats); 3] {
+        [
+            ("Service time", &self.service_time),
+            ("Corrected latency", &self.corrected_latency),
+            ("Start lag", &self.start_lag),
+        ]
+    }
+}
+
+/// What one stage of a staged run achieved
+pub struct StageStats {
+    pub stage: Stage,
+    pub sent: u64,
+    pub accepted: u64,
+    pub failed: u64,
+    /// Accepted but not included before the inclusion timeout
+    pub dropped: u64,
+    pub inclusion: LatencyStats,
+}
+
+impl StageStats {
+    /// Groups the records by stage, in stage order
+    pub fn from_records(stages: &[Stage], records: &[SendRecord]) -> Vec<Self> {
+        let mut stats = stages
+            .iter()
+            .map(|stage| Self {
+                stage: *stage,
+                sent: 0,
+                accepted: 0,
+                failed: 0,
+                dropped: 0,
+                inclusion: LatencyStats::new(),
+            })
+            .collect::<Vec<_>>();
+        for record in records {
+            let stage = &mut stats[record.stage];
+            stage.sent += 1;
+            if record.result.is_err() {
+                stage.failed += 1;
+                continue;
+            }
+            stage.accepted += 1;
+            match record.inclusion_latency() {
+                Some(latency) => stage.inclusion.record(latency),
+                None => stage.dropped += 1,
+            }
+        }
+        stats
+    }
+
+    /// Average rate the stage was scheduled at
+    pub fn target_rate(&self) -> f64 {
+        (self.stage.from_rate + self.stage.to_rate) / 2.0
+    }
+
+    /// Share of sends the node accepted, in percent
+    pub fn acceptance_rate(&self) -> f64 {
+        percent(self.accepted, self.sent)
+    }
+
+    /// Share of sends that were rejected or never included, in percent
+    pub fn error_rate(&self) -> f64 {
+        percent(self.failed + self.dropped, self.sent)
+    }
+}
+
+/// Limits past which a stage counts as saturated
+#[derive(Debug, Clone, Copy)]
+pub struct KneeThresholds {
+    /// p99 inclusion latency in ms; `None` means twice the first stage's p99
+    pub max_p99_ms: Option<f64>,
+    /// Error rate in percent
+    pub max_error_rate: f64,
+}
+
+/// Index of the first stage whose p99 inclusion latency or error rate
+/// crosses the thresholds, and the reason
+pub fn knee(stages: &[StageStats], thresholds: &KneeThresholds) -> Option<(usize, String)> {
+    let max_p99_ms = thresholds.max_p99_ms.or_else(|| {
+        stages
+            .first()
+            .filter(|stage| !stage.inclusion.is_empty())
+            .map(|stage| stage.inclusion.p99_ms() * 2.0)
+    });
+
+    stages.iter().enumerate().find_map(|(i, stage)| {
+        if stage.error_rate() > thresholds.max_error_rate {
+            return Some((i, format!("error rate {:.1}% is above {:.1}%", stage.error_rate(), thresholds.max_error_rate)));
+        }
+        match max_p99_ms {
+            Some(max) if !stage.inclusion.is_empty() && stage.inclusion.p99_ms() > max => {
+                Some((i, format!("p99 inclusion latency {:.2} ms is above {:.2} ms", stage.inclusion.p99_ms(), max)))
+            }
+            _ => None,
+        }
+    })
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: f64) -> Duration {
+        Duration::from_secs_f64(secs)
+    }
+
+    fn rates(stages: &[Stage]) -> Vec<(f64, f64)> {
+        stages.iter().map(|stage| (stage.from_rate, stage.to_rate)).collect()
+    }
+
+    #[test]
+    fn step_holds_each_rate_from_start_to_peak() {
+        let stages = plan(LoadProfile::Step, 10.0, 50.0, 5, secs(30.0)).unwrap();
+        assert_eq!(rates(&stages), vec![(10.0, 10.0), (20.0, 20.0), (30.0, 30.0), (40.0, 40.0), (50.0, 50.0)]);
+        let starts = stages.iter().map(|stage| stage.start).collect::<Vec<_>>();
+        assert_eq!(starts, vec![secs(0.0), secs(30.0), secs(60.0), secs(90.0), secs(120.0)]);
+        assert!(stages.iter().all(|stage| stage.duration == secs(30.0)));
+    }
+
+    #[test]
+    fn single_step_runs_at_the_peak() {
+        let stages = plan(LoadProfile::Step, 10.0, 50.0, 1, secs(30.0)).unwrap();
+        assert_eq!(rates(&stages), vec![(50.0, 50.0)]);
+    }
+
+    #[test]
+    fn ramp_stages_join_up() {
+        let stages = plan(LoadProfile::Ramp, 10.0, 50.0, 4, secs(10.0)).unwrap();
+        assert_eq!(rates(&stages), vec![(10.0, 20.0), (20.0, 30.0), (30.0, 40.0), (40.0, 50.0)]);
+        assert_eq!(stages[3].start, secs(30.0));
+    }
+
+    #[test]
+    fn spike_always_has_three_stages() {
+        let stages = plan(LoadProfile::Spike, 10.0, 100.0, 7, secs(5.0)).unwrap();
+        assert_eq!(rates(&stages), vec![(10.0, 10.0), (100.0, 100.0), (10.0, 10.0)]);
+        assert_eq!(stages[2].start, secs(10.0));
+    }
+
+    #[test]
+    fn tx_count_integrates_the_rate() {
+        assert_eq!(Stage::constant(10.0, secs(30.0)).tx_count(), 300);
+        let ramp = Stage { start: Duration::ZERO, duration: secs(10.0), from_rate: 10.0, to_rate: 20.0 };
+        assert_eq!(ramp.tx_count(), 150);
+        assert_eq!(Stage::constant(0.1, secs(1.0)).tx_count(), 1);
+    }
+
+    #[test]
+    fn offsets_follow_the_rate() {
+        let constant = Stage::constant(10.0, secs(1.0));
+        assert_eq!(constant.offset(0), Duration::ZERO);
+        assert_eq!(constant.offset(5), secs(0.5));
+
+        // 10 tx/s rising to 30 tx/s over 2s sends 10t + 5t² transactions by t
+        let ramp = Stage { start: Duration::ZERO, duration: secs(2.0), from_rate: 10.0, to_rate: 30.0 };
+        assert!(ramp.offset(15).abs_diff(secs(1.0)) < Duration::from_micros(1));
+        assert!(ramp.offset(40).abs_diff(secs(2.0)) < Duration::from_micros(1));
+    }
+
+    #[test]
+    fn schedule_places_every_stage_after_the_last() {
+        let stages = plan(LoadProfile::Step, 1.0, 2.0, 2, secs(2.0)).unwrap();
+        assert_eq!(
+            schedule(&stages).unwrap(),
+            vec![
+                (secs(0.0), 0),
+                (secs(1.0), 0),
+                (secs(2.0), 1),
+                (secs(2.5), 1),
+                (secs(3.0), 1),
+                (secs(3.5), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_rejects_a_run_too_long_for_a_duration() {
+        assert!(plan(LoadProfile::Step, 1.0, 2.0, 5, Duration::MAX / 4).is_err());
+        assert!(plan(LoadProfile::Spike, 1.0, 2.0, 1, Duration::MAX / 2).is_err());
+        assert!(plan(LoadProfile::Step, 1.0, 2.0, 4, Duration::MAX / 4).is_ok());
+    }
+
+    #[test]
+    fn schedule_caps_the_transaction_count() {
+        assert!(schedule(&[Stage::constant(1e300, secs(1.0))]).is_err());
+        assert!(schedule(&[Stage::constant(f64::INFINITY, secs(1.0))]).is_err());
+        assert!(schedule(&[Stage::constant(1000.0, secs(3600.0))]).is_err());
+        let halves = [Stage::constant(1000.0, secs(500.0)), Stage::constant(1000.0, secs(500.0))];
+        assert_eq!(total_tx_count(&halves).unwrap(), MAX_SCHEDULED_TXS);
+        let stages = [Stage::constant(1000.0, secs(500.0)), Stage::constant(1000.0, secs(500.001))];
+        assert!(total_tx_count(&stages).is_err());
+    }
+
+    fn stage_stats(p99_ms: f64, sent: u64, dropped: u64) -> StageStats {
+        let included = sent - dropped;
+        StageStats {
+            stage: Stage::constant(10.0, secs(1.0)),
+            sent,
+            accepted: sent,
+            failed: 0,
+            dropped,
+            inclusion: LatencyStats::from_durations((0..included).map(|_| secs(p99_ms / 1000.0))),
+        }
+    }
+
+    #[test]
+    fn knee_defaults_to_twice_the_first_p99() {
+        let stages = [100.0, 120.0, 150.0, 260.0, 400.0].map(|p99| stage_stats(p99, 100, 0));
+        let thresholds = KneeThresholds { max_p99_ms: None, max_error_rate: 1.0 };
+        let (i, reason) = knee(&stages, &thresholds).unwrap();
+        assert_eq!(i, 3);
+        assert!(reason.starts_with("p99 inclusion latency"), "{}", reason);
+    }
+
+    #[test]
+    fn knee_uses_the_given_p99_limit() {
+        let stages = [100.0, 120.0, 150.0, 260.0].map(|p99| stage_stats(p99, 100, 0));
+        let thresholds = KneeThresholds { max_p99_ms: Some(140.0), max_error_rate: 1.0 };
+        assert_eq!(knee(&stages, &thresholds).map(|(i, _)| i), Some(2));
+    }
+
+    #[test]
+    fn knee_on_error_rate() {
+        let stages = [stage_stats(100.0, 100, 0), stage_stats(110.0, 100, 5), stage_stats(120.0, 100, 0)];
+        let thresholds = KneeThresholds { max_p99_ms: None, max_error_rate: 1.0 };
+        let (i, reason) = knee(&stages, &thresholds).unwrap();
+        assert_eq!(i, 1);
+        assert!(reason.starts_with("error rate 5.0%"), "{}", reason);
+    }
+
+    #[test]
+    fn no_knee_within_thresholds() {
+        let stages = [100.0, 110.0, 190.0].map(|p99| stage_stats(p99, 100, 0));
+        let thresholds = KneeThresholds { max_p99_ms: None, max_error_rate: 1.0 };
+        assert!(knee(&stages, &thresholds).is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How a transaction is submitted and confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// Regular sendTransaction followed by receipt polling
+    Async,
+    /// Signed locally and sent with `eth_sendRawTransactionSync`
+    Rise,
+    /// Signed locally and sent with `realtime_sendRawTransaction`
+    Mega,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Async => "async",
+            Method::Rise => "rise",
+            Method::Mega => "mega",
+        }
+    }
+
+    /// The RPC method used to submit the transaction
+    pub fn rpc_method(&self) -> &'static str {
+        match self {
+            Method::Async => "eth_sendRawTransaction",
+            Method::Rise => "eth_sendRawTransactionSync",
+            Method::Mega => "realtime_sendRawTransaction",
+        }
+    }
+
+    /// Whether the submission call itself returns the receipt
+    pub fn is_sync(&self) -> bool {
+        !matches!(self, Method::Async)
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,306 @@
+use anyhow::Result;
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber, H256},
+};
+use futures::future::join_all;
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    client::Client,
+    gas::Fees,
+    latency::send_transaction,
+    tx::{self, TxType},
+};
+
+/// Hands out consecutive nonces for one sender and records which ones the
+/// node accepted, so the gaps left by failed sends can be found and filled
+#[derive(Debug, Clone)]
+pub struct NonceManager {
+    address: Address,
+    start: u64,
+    next: u64,
+    accepted: BTreeSet<u64>,
+    failed: BTreeSet<u64>,
+}
+
+impl NonceManager {
+    pub fn new(address: Address, start: u64) -> Self {
+        Self {
+            address,
+            start,
+            next: start,
+            accepted: BTreeSet::new(),
+            failed: BTreeSet::new(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Hands out the next nonce
+    pub fn next_nonce(&mut self) -> u64 {
+        let nonce = self.next;
+        self.next += 1;
+        nonce
+    }
+
+    pub fn mark_accepted(&mut self, nonce: u64) {
+        self.failed.remove(&nonce);
+        self.accepted.insert(nonce);
+    }
+
+    pub fn mark_failed(&mut self, nonce: u64) {
+        if !self.accepted.contains(&nonce) {
+            self.failed.insert(nonce);
+        }
+    }
+
+    pub fn accepted(&self) -> usize {
+        self.accepted.len()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.len()
+    }
+
+    /// Failed nonces below the highest accepted one. Every transaction
+    /// after a gap stays stuck in the mempool until the gap is filled.
+    pub fn gaps(&self) -> Vec<u64> {
+        match self.accepted.last() {
+            Some(highest) => self.failed.range(..highest).copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Gaps the node doesn't hold, lowest first, given its `pending` nonce.
+    /// The node holds everything below it, so those sends went through even
+    /// though they returned an error and are marked accepted.
+    pub fn gaps_to_fill(&mut self, pending: u64) -> Vec<u64> {
+        let (held, missing): (Vec<_>, Vec<_>) = self.gaps().into_iter().partition(|nonce| *nonce < pending);
+        for nonce in held {
+            self.mark_accepted(nonce);
+        }
+        missing
+    }
+
+    /// Nonce the node reports as pending once every accepted transaction
+    /// is lined up without gaps
+    pub fn expected_pending(&self) -> u64 {
+        self.accepted.last().map_or(self.start, |highest| highest + 1)
+    }
+}
+
+/// Hands out one sender's nonces to concurrent workers. A nonce whose send
+/// failed is handed out again before any new one, so it is filled without
+/// rewinding past the nonces other workers have in flight.
+#[derive(Debug)]
+pub struct SharedNonces {
+    next: AtomicU64,
+    reissued: Mutex<BTreeSet<u64>>,
+}
+
+impl SharedNonces {
+    pub fn new(start: u64) -> Self {
+        Self {
+            next: AtomicU64::new(start),
+            reissued: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// The lowest nonce to reissue, or else the next new one
+    pub fn take(&self) -> u64 {
+        match self.reissued.lock().unwrap().pop_first() {
+            Some(nonce) => nonce,
+            None => self.next.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Hands `nonce` out again, for a send the node never got
+    pub fn reissue(&self, nonce: u64) {
+        self.reissued.lock().unwrap().insert(nonce);
+    }
+}
+
+/// The node rejected a send because its nonce is already used, by a mined
+/// transaction or one waiting in the mempool
+pub fn is_nonce_taken(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_lowercase();
+    message.contains("nonce too low") || message.contains("already known")
+}
+
+/// An account's nonces as seen by the node, against what the manager expects
+#[derive(Debug, Clone, Copy)]
+pub struct NonceStatus {
+    pub address: Address,
+    /// Nonce of the next transaction to be mined
+    pub latest: u64,
+    /// Nonce after the last transaction lined up in the mempool
+    pub pending: u64,
+    pub expected: u64,
+}
+
+impl NonceStatus {
+    pub async fn fetch(client: &Client, manager: &NonceManager) -> Result<Self> {
+        let address = manager.address();
+        let (latest, pending) = nonces(client, address).await?;
+        Ok(Self {
+            address,
+            latest,
+            pending,
+            expected: manager.expected_pending(),
+        })
+    }
+
+    /// Accepted transactions are queued behind a nonce the node never got
+    pub fn is_stuck(&self) -> bool {
+        self.pending < self.expected
+    }
+}
+
+/// Nonce of the account's next transaction after everything lined up in
+/// the mempool
+pub async fn pending_nonce(client: &Client, address: Address) -> Result<u64> {
+    Ok(client.get_transaction_count(address, Some(BlockNumber::Pending.into())).await?.as_u64())
+}
+
+/// The account's `latest` and `pending` nonces. Every nonce in between
+/// belongs to a transaction waiting in the mempool.
+pub async fn nonces(client: &Client, address: Address) -> Result<(u64, u64)> {
+    let latest = client.get_transaction_count(address, Some(BlockNumber::Latest.into())).await?;
+    Ok((latest.as_u64(), pending_nonce(client, address).await?))
+}
+
+/// Sends a zero-value self transfer at every gap nonce the node is not
+/// already holding, at bumped replacement fees so it also replaces anything
+/// that did reach the mempool. Returns the result for every filled nonce.
+pub async fn fill_gaps(
+    client: Arc<Client>,
+    manager: &mut NonceManager,
+    tx_type: TxType,
+    fees: &Fees,
+) -> Result<Vec<(u64, Result<H256>)>> {
+    let pending = pending_nonce(&client, manager.address()).await?;
+    let fees = fees.bumped();
+    let address = manager.address();
+
+    let fills = manager
+        .gaps_to_fill(pending)
+        .into_iter()
+        .map(|nonce| {
+            let client = client.clone();
+            async move {
+                let tx = tx::self_transfer(tx_type, address, Some(nonce), &fees);
+                (nonce, send_transaction(client, tx).await)
+            }
+        });
+    let results = join_all(fills).await;
+
+    for (nonce, result) in &results {
+        if result.is_ok() {
+            manager.mark_accepted(*nonce);
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager_with(accepted: &[u64], failed: &[u64]) -> NonceManager {
+        let mut manager = NonceManager::new(Address::zero(), 10);
+        for _ in 0..accepted.len() + failed.len() {
+            manager.next_nonce();
+        }
+        for nonce in accepted {
+            manager.mark_accepted(*nonce);
+        }
+        for nonce in failed {
+            manager.mark_failed(*nonce);
+        }
+        manager
+    }
+
+    #[test]
+    fn hands_out_consecutive_nonces() {
+        let mut manager = NonceManager::new(Address::zero(), 7);
+        assert_eq!((manager.next_nonce(), manager.next_nonce(), manager.next_nonce()), (7, 8, 9));
+        assert_eq!(manager.start(), 7);
+        assert_eq!(manager.expected_pending(), 7);
+    }
+
+    #[test]
+    fn gaps_are_failed_nonces_below_the_highest_accepted() {
+        let manager = manager_with(&[10, 12, 14], &[13, 11, 15]);
+        assert_eq!(manager.gaps(), vec![11, 13]);
+        assert_eq!((manager.accepted(), manager.failed()), (3, 3));
+        assert_eq!(manager.expected_pending(), 15);
+    }
+
+    #[test]
+    fn no_gaps_without_accepted_nonces() {
+        let manager = manager_with(&[], &[10, 11]);
+        assert!(manager.gaps().is_empty());
+        assert_eq!(manager.expected_pending(), 10);
+    }
+
+    #[test]
+    fn accepted_nonces_stay_accepted() {
+        let mut manager = manager_with(&[10, 11], &[]);
+        manager.mark_failed(11);
+        assert_eq!((manager.accepted(), manager.failed()), (2, 0));
+
+        // A retried nonce that goes through is no longer a failure
+        let mut manager = manager_with(&[10, 12], &[11]);
+        manager.mark_accepted(11);
+        assert!(manager.gaps().is_empty());
+        assert_eq!(manager.failed(), 0);
+    }
+
+    #[test]
+    fn gaps_the_node_holds_are_not_filled() {
+        let mut manager = manager_with(&[10, 12, 14, 16], &[11, 13, 15]);
+        assert_eq!(manager.gaps_to_fill(14), vec![15]);
+        assert_eq!(manager.gaps(), vec![15]);
+
+        manager.mark_accepted(15);
+        assert!(manager.gaps().is_empty());
+        assert_eq!(manager.expected_pending(), 17);
+    }
+
+    #[test]
+    fn gaps_are_filled_lowest_first() {
+        let mut manager = manager_with(&[10, 20], &[17, 12, 15]);
+        assert_eq!(manager.gaps_to_fill(10), vec![12, 15, 17]);
+        assert_eq!(manager.failed(), 3);
+    }
+
+    #[test]
+    fn reissued_nonces_come_first_lowest_first() {
+        let nonces = SharedNonces::new(5);
+        assert_eq!((nonces.take(), nonces.take(), nonces.take()), (5, 6, 7));
+        nonces.reissue(7);
+        nonces.reissue(5);
+        assert_eq!((nonces.take(), nonces.take(), nonces.take()), (5, 7, 8));
+    }
+
+    #[test]
+    fn recognizes_taken_nonces() {
+        assert!(is_nonce_taken(&anyhow::anyhow!("(code: -32000, message: nonce too low, data: None)")));
+        assert!(is_nonce_taken(&anyhow::anyhow!("Already Known").context("send failed")));
+        assert!(!is_nonce_taken(&anyhow::anyhow!("insufficient funds for gas * price + value")));
+        assert!(!is_nonce_taken(&anyhow::anyhow!("replacement transaction underpriced")));
+    }
+}
@@ -0,0 +1,27 @@
+use ethers::types::TransactionReceipt;
+
+/// Returns the receipt status in a readable form
+pub fn status_str(receipt: &TransactionReceipt) -> &'static str {
+    match receipt.status {
+        Some(status) if status.low_u32() == 1 => "SUCCESS",
+        Some(_) => "FAILED",
+        None => "UNKNOWN",
+    }
+}
+
+/// Prints the receipt block shown after every confirmed transaction
+pub fn print_receipt(receipt: &TransactionReceipt) {
+    println!("\n====== TRANSACTION RECEIPT ======");
+    println!("Transaction Hash: {:?}", receipt.transaction_hash);
+    println!("Transaction Status: {}", status_str(receipt));
+    println!("Block Number: {:?}", receipt.block_number);
+    println!("Gas Used: {:?}", receipt.gas_used);
+    println!("================================");
+}
+
+/// Prints the block the transaction was included in, if known
+pub fn print_inclusion(receipt: &TransactionReceipt) {
+    if let Some(block_number) = receipt.block_number {
+        println!("Included in block: {}", block_number);
+    }
+}
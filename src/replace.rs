@@ -0,0 +1,253 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::{eip1559::Eip1559TransactionRequest, eip2718::TypedTransaction},
+        Address, Transaction, TransactionRequest, H256,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+use crate::{
+    client::Client,
+    gas::Fees,
+    latency::send_transaction,
+    nonce,
+    tx::{self, TxType},
+};
+
+/// How often the nonces are polled while waiting for an account to unblock
+const UNBLOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What is sent in place of a stuck transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Replacement {
+    /// A zero-value self transfer, dropping the original
+    Cancel,
+    /// The original transaction again with bumped fees
+    SpeedUp,
+}
+
+impl fmt::Display for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Replacement::Cancel => f.write_str("cancel"),
+            Replacement::SpeedUp => f.write_str("speed-up"),
+        }
+    }
+}
+
+/// An account's mempool as returned by `txpool_contentFrom`, keyed by nonce
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TxpoolContentFrom {
+    #[serde(default)]
+    pending: BTreeMap<String, Transaction>,
+    #[serde(default)]
+    queued: BTreeMap<String, Transaction>,
+}
+
+/// The account's transactions in the node's mempool, by nonce. Uses the
+/// non-standard `txpool_contentFrom` (geth, reth and most of their forks).
+pub async fn mempool_transactions(client: &Client, address: Address) -> Result<BTreeMap<u64, Transaction>> {
+    let content: TxpoolContentFrom = client
+        .provider()
+        .request("txpool_contentFrom", [address])
+        .await?;
+    Ok(content
+        .pending
+        .into_values()
+        .chain(content.queued.into_values())
+        .map(|tx| (tx.nonce.as_u64(), tx))
+        .collect())
+}
+
+/// Fees to replace a transaction with: `fees`, raised where needed to a
+/// bump over what the original pays so the node accepts the replacement.
+/// Without the original, `fees` are bumped in case it paid them.
+pub fn replacement_fees(fees: &Fees, original: Option<&Transaction>) -> Fees {
+    match original {
+        Some(original) => fees.max(&Fees::paid_by(original).bumped()),
+        None => fees.bumped(),
+    }
+}
+
+/// Builds the replacement at `nonce`. Speeding up without the original
+/// falls back to a cancel, since there is nothing to resend.
+pub fn replacement_tx(
+    replacement: Replacement,
+    tx_type: TxType,
+    address: Address,
+    nonce: u64,
+    original: Option<&Transaction>,
+    fees: &Fees,
+) -> TypedTransaction {
+    match (replacement, original) {
+        (Replacement::SpeedUp, Some(original)) => resend(original, fees),
+        _ => tx::self_transfer(tx_type, address, Some(nonce), fees),
+    }
+}
+
+/// The original transaction with new fees, keeping its type
+fn resend(original: &Transaction, fees: &Fees) -> TypedTransaction {
+    let access_list = original.access_list.clone().unwrap_or_default();
+    let mut tx = match original.transaction_type.map(|t| t.as_u64()) {
+        Some(1) => TypedTransaction::Eip2930(
+            TransactionRequest::new()
+                .gas_price(fees.gas_price)
+                .with_access_list(access_list),
+        ),
+        Some(2) => TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .access_list(access_list)
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas),
+        ),
+        _ => TypedTransaction::Legacy(TransactionRequest::new().gas_price(fees.gas_price)),
+    };
+    if let Some(to) = original.to {
+        tx.set_to(to);
+    }
+    tx.set_value(original.value);
+    tx.set_data(original.input.clone());
+    tx.set_nonce(original.nonce);
+    tx.set_gas(original.gas);
+    tx
+}
+
+/// Sends the replacement built by `build`, bumping the fees again each
+/// time the node rejects it as underpriced, up to `attempts` sends.
+/// Returns the hash and the fees that were accepted.
+pub async fn send_replacement(
+    client: Arc<Client>,
+    mut fees: Fees,
+    attempts: u32,
+    build: impl Fn(&Fees) -> TypedTransaction,
+) -> Result<(H256, Fees)> {
+    let mut attempt = 1;
+    loop {
+        match send_transaction(client.clone(), build(&fees)).await {
+            Ok(hash) => return Ok((hash, fees)),
+            Err(e) if attempt < attempts && e.to_string().to_lowercase().contains("underpriced") => {
+                println!("Replacement underpriced at {}, bumping", fees);
+                fees = fees.bumped();
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Polls until the node's latest nonce reaches `target`, so every stuck
+/// transaction was replaced and mined. Returns the final latest and
+/// pending nonces.
+pub async fn wait_unblocked(client: &Client, address: Address, target: u64, timeout: Duration) -> Result<(u64, u64)> {
+    let start = Instant::now();
+    loop {
+        let (latest, pending) = nonce::nonces(client, address).await?;
+        if latest >= target && latest >= pending {
+            return Ok((latest, pending));
+        }
+        if start.elapsed() >= timeout {
+            bail!("{:?} is still blocked after {:?}: latest nonce {}, pending nonce {}",
+                  address, timeout, latest, pending);
+        }
+        sleep(UNBLOCK_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    fn fees(gwei: u64) -> Fees {
+        let fee = U256::from(gwei * GWEI);
+        Fees { gas_price: fee, max_fee_per_gas: fee, max_priority_fee_per_gas: fee }
+    }
+
+    fn legacy(gas_price_gwei: u64) -> Transaction {
+        Transaction {
+            gas_price: Some(U256::from(gas_price_gwei * GWEI)),
+            nonce: 5.into(),
+            gas: 21000.into(),
+            to: Some(Address::repeat_byte(1)),
+            ..Default::default()
+        }
+    }
+
+    fn eip1559(max_fee_gwei: u64, priority_fee_gwei: u64) -> Transaction {
+        Transaction {
+            transaction_type: Some(2.into()),
+            max_fee_per_gas: Some(U256::from(max_fee_gwei * GWEI)),
+            max_priority_fee_per_gas: Some(U256::from(priority_fee_gwei * GWEI)),
+            ..legacy(0)
+        }
+    }
+
+    /// Whether `fee` is more than 10% above `original`, as nodes require
+    fn outbids(fee: U256, original: U256) -> bool {
+        fee * 10 > original * 11
+    }
+
+    #[test]
+    fn bumps_over_the_original_fees() {
+        let original = eip1559(20, 3);
+        let replacement = replacement_fees(&fees(1), Some(&original));
+        assert!(outbids(replacement.max_fee_per_gas, original.max_fee_per_gas.unwrap()));
+        assert!(outbids(replacement.max_priority_fee_per_gas, original.max_priority_fee_per_gas.unwrap()));
+
+        let original = legacy(20);
+        let replacement = replacement_fees(&fees(1), Some(&original));
+        assert!(outbids(replacement.gas_price, original.gas_price.unwrap()));
+    }
+
+    #[test]
+    fn keeps_fees_already_above_the_original() {
+        assert_eq!(replacement_fees(&fees(50), Some(&eip1559(20, 3))), fees(50));
+        assert_eq!(replacement_fees(&fees(50), Some(&legacy(20))), fees(50));
+    }
+
+    #[test]
+    fn bumps_the_fees_without_an_original() {
+        assert_eq!(replacement_fees(&fees(10), None), fees(10).bumped());
+    }
+
+    #[test]
+    fn speed_up_keeps_the_original_type() {
+        let new_fees = fees(30);
+        let tx = replacement_tx(Replacement::SpeedUp, TxType::Legacy, Address::zero(), 5, Some(&eip1559(20, 3)), &new_fees);
+        let TypedTransaction::Eip1559(tx) = tx else {
+            panic!("EIP-1559 original resent as {:?}", tx);
+        };
+        assert_eq!(tx.max_fee_per_gas, Some(new_fees.max_fee_per_gas));
+        assert_eq!(tx.nonce, Some(5.into()));
+        assert_eq!(tx.gas, Some(21000.into()));
+
+        let tx = replacement_tx(Replacement::SpeedUp, TxType::Eip1559, Address::zero(), 5, Some(&legacy(20)), &new_fees);
+        let TypedTransaction::Legacy(tx) = tx else {
+            panic!("legacy original resent as {:?}", tx);
+        };
+        assert_eq!(tx.gas_price, Some(new_fees.gas_price));
+        assert_eq!(tx.to, Some(Address::repeat_byte(1).into()));
+    }
+
+    #[test]
+    fn cancel_is_a_self_transfer_of_the_chosen_type() {
+        let address = Address::repeat_byte(2);
+        let tx = replacement_tx(Replacement::Cancel, TxType::Legacy, address, 7, Some(&eip1559(20, 3)), &fees(30));
+        assert!(matches!(tx, TypedTransaction::Legacy(_)));
+        assert_eq!(tx.to_addr(), Some(&address));
+        assert_eq!(tx.nonce(), Some(&7.into()));
+        assert_eq!(tx.value(), Some(&U256::zero()));
+    }
+}
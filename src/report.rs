@@ -0,0 +1,689 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use ethers::types::{Address, TransactionReceipt, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    confirm::ConfirmStrategy,
+    gas::{format_gwei, GasStrategy},
+    method::Method,
+    receipt,
+    stats::{Metric, RunStats},
+    tx::TxType,
+};
+
+/// Directory reports are written to
+pub const RESULTS_DIR: &str = "results";
+
+/// Output format of a saved report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Human readable summary with percentile tables and histograms
+    Markdown,
+    /// The whole `RunReport` as a single JSON document
+    Json,
+    /// One JSON object per transaction, each carrying the run metadata
+    Ndjson,
+    /// One CSV row per transaction, each carrying the run metadata
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Json => "json",
+            ReportFormat::Ndjson => "ndjson",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Outcome of a single transaction, as recorded in a report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxRecord {
+    pub index: u64,
+    pub hash: H256,
+    /// Wallet that sent the transaction, zero in reports written before
+    /// multi-wallet runs
+    #[serde(default)]
+    pub sender: Address,
+    pub nonce: u64,
+    pub send_ms: f64,
+    pub confirm_ms: f64,
+    pub total_ms: f64,
+    pub block_number: Option<u64>,
+    pub gas_used: Option<u64>,
+    pub status: String,
+}
+
+impl TxRecord {
+    /// Builds a record from a receipt and the measured timings
+    pub fn new(
+        index: u64,
+        nonce: u64,
+        receipt: &TransactionReceipt,
+        send_time: Duration,
+        confirm_time: Duration,
+        total_time: Duration,
+    ) -> Self {
+        Self {
+            index,
+            hash: receipt.transaction_hash,
+            sender: receipt.from,
+            nonce,
+            send_ms: duration_ms(send_time),
+            confirm_ms: duration_ms(confirm_time),
+            total_ms: duration_ms(total_time),
+            block_number: receipt.block_number.map(|b| b.as_u64()),
+            gas_used: receipt.gas_used.map(|g| g.low_u64()),
+            status: receipt::status_str(receipt).to_lowercase(),
+        }
+    }
+
+    pub fn send_time(&self) -> Duration {
+        ms_duration(self.send_ms)
+    }
+
+    pub fn confirm_time(&self) -> Duration {
+        ms_duration(self.confirm_ms)
+    }
+
+    pub fn total_time(&self) -> Duration {
+        ms_duration(self.total_ms)
+    }
+}
+
+/// A complete latency run: metadata plus per-transaction timings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub test_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub rpc_url: String,
+    pub chain_id: u64,
+    /// First sender wallet
+    pub wallet: Address,
+    /// Every wallet that sent transactions, empty in reports written before
+    /// multi-wallet runs
+    #[serde(default)]
+    pub senders: Vec<Address>,
+    pub method: Method,
+    /// Transaction type sent, missing in older reports
+    #[serde(default)]
+    pub tx_type: Option<TxType>,
+    /// Gas price in wei, as a decimal string so it never overflows
+    pub gas_price_wei: String,
+    /// Strategy the fees were chosen with, missing in older reports
+    #[serde(default)]
+    pub gas_strategy: Option<GasStrategy>,
+    /// EIP-1559 fees in wei, missing in older reports
+    #[serde(default)]
+    pub max_fee_per_gas_wei: Option<String>,
+    #[serde(default)]
+    pub max_priority_fee_per_gas_wei: Option<String>,
+    /// How async sends were confirmed, missing for sync methods and in
+    /// older reports
+    #[serde(default)]
+    pub confirm_strategy: Option<ConfirmStrategy>,
+    /// Workers keeping one transaction in flight each, for closed-loop runs
+    #[serde(default)]
+    pub concurrency: Option<u64>,
+    pub total_duration_ms: f64,
+    /// Written while the run was still going, as a snapshot of a
+    /// duration-based run
+    #[serde(default)]
+    pub interim: bool,
+    /// Transactions of the run that were never confirmed: send errors,
+    /// ones still unconfirmed when a duration-based run ended, or ones an
+    /// aborted run never sent. Zero in older reports.
+    #[serde(default)]
+    pub failed_txs: u64,
+    pub transactions: Vec<TxRecord>,
+}
+
+/// One transaction together with the run it belongs to, used for the
+/// row-oriented formats (NDJSON and CSV)
+#[derive(Serialize)]
+struct TxRow<'a> {
+    test_name: Option<&'a str>,
+    timestamp: DateTime<Utc>,
+    rpc_url: &'a str,
+    chain_id: u64,
+    method: Method,
+    tx_type: Option<TxType>,
+    gas_strategy: Option<&'a GasStrategy>,
+    confirm_strategy: Option<&'a ConfirmStrategy>,
+    failed_txs: u64,
+    #[serde(flatten)]
+    tx: &'a TxRecord,
+}
+
+impl RunReport {
+    /// Every transaction's `metric` in milliseconds
+    pub fn samples(&self, metric: Metric) -> Vec<f64> {
+        self.transactions
+            .iter()
+            .map(|tx| match metric {
+                Metric::Send => tx.send_ms,
+                Metric::Confirm => tx.confirm_ms,
+                Metric::Total => tx.total_ms,
+            })
+            .collect()
+    }
+
+    /// Confirmed transactions per second over the whole run
+    pub fn throughput(&self) -> f64 {
+        if self.total_duration_ms > 0.0 {
+            self.transactions.len() as f64 * 1000.0 / self.total_duration_ms
+        } else {
+            0.0
+        }
+    }
+
+    /// Send, confirm and total time distributions over all transactions
+    pub fn stats(&self) -> RunStats {
+        RunStats::from_timings(
+            self.transactions
+                .iter()
+                .map(|tx| (tx.send_time(), tx.confirm_time(), tx.total_time())),
+        )
+    }
+
+    /// Stats of each sender wallet's transactions, in sender order
+    pub fn sender_stats(&self) -> Vec<(Address, RunStats)> {
+        self.senders
+            .iter()
+            .map(|sender| {
+                let stats = RunStats::from_timings(
+                    self.transactions
+                        .iter()
+                        .filter(|tx| tx.sender == *sender)
+                        .map(|tx| (tx.send_time(), tx.confirm_time(), tx.total_time())),
+                );
+                (*sender, stats)
+            })
+            .collect()
+    }
+
+    /// Prints a row of total time percentiles per sender wallet, when the
+    /// run used more than one
+    pub fn print_sender_stats(&self) {
+        if self.senders.len() < 2 {
+            return;
+        }
+
+        println!("\nPER SENDER (total time, ms):");
+        println!("{:<42} {:>6} {:>9} {:>9} {:>9} {:>9}", "SENDER", "TXS", "P50", "P99", "MAX", "AVG");
+        println!("{}", "-".repeat(90));
+        for (sender, stats) in self.sender_stats() {
+            let total = &stats.total;
+            println!("{:<42} {:>6} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+                     format!("{:?}", sender), total.count(), total.p50_ms(), total.p99_ms(), total.max_ms(), total.mean_ms());
+        }
+    }
+
+    /// File name stem: `<test name>-<timestamp>` or `rpc-test-<timestamp>`
+    pub fn file_stem(&self) -> String {
+        let timestamp = self.timestamp.format("%Y-%m-%d-%H%M%S");
+        match &self.test_name {
+            Some(test_name) => format!("{}-{}", test_name, timestamp),
+            None => format!("rpc-test-{}", timestamp),
+        }
+    }
+
+    /// Writes the report into `dir` once per format and returns the paths
+    pub fn save(&self, dir: &Path, formats: &[ReportFormat]) -> Result<Vec<PathBuf>> {
+        write_formats(dir, &self.file_stem(), formats, |format| self.render(format))
+    }
+
+    /// Loads a report previously saved as JSON
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {} as a JSON run report", path.display()))
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Ndjson => self.to_ndjson()?,
+            ReportFormat::Csv => self.to_csv(),
+        })
+    }
+
+    fn rows(&self) -> impl Iterator<Item = TxRow<'_>> {
+        self.transactions.iter().map(move |tx| TxRow {
+            test_name: self.test_name.as_deref(),
+            timestamp: self.timestamp,
+            rpc_url: &self.rpc_url,
+            chain_id: self.chain_id,
+            method: self.method,
+            tx_type: self.tx_type,
+            gas_strategy: self.gas_strategy.as_ref(),
+            confirm_strategy: self.confirm_strategy.as_ref(),
+            failed_txs: self.failed_txs,
+            tx,
+        })
+    }
+
+    pub fn to_ndjson(&self) -> Result<String> {
+        let mut out = String::new();
+        for row in self.rows() {
+            out.push_str(&serde_json::to_string(&row)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "test_name,timestamp,rpc_url,chain_id,method,tx_type,gas_strategy,confirm_strategy,failed_txs,\
+             index,hash,sender,nonce,send_ms,confirm_ms,total_ms,block_number,gas_used,status\n",
+        );
+        for row in self.rows() {
+            let fields = [
+                csv_field(row.test_name.unwrap_or("")),
+                row.timestamp.to_rfc3339(),
+                csv_field(row.rpc_url),
+                row.chain_id.to_string(),
+                row.method.to_string(),
+                row.tx_type.map(|tx_type| tx_type.to_string()).unwrap_or_default(),
+                row.gas_strategy.map(|strategy| csv_field(&strategy.to_string())).unwrap_or_default(),
+                row.confirm_strategy.map(|strategy| csv_field(&strategy.to_string())).unwrap_or_default(),
+                row.failed_txs.to_string(),
+                row.tx.index.to_string(),
+                format!("{:?}", row.tx.hash),
+                format!("{:?}", row.tx.sender),
+                row.tx.nonce.to_string(),
+                format!("{:.3}", row.tx.send_ms),
+                format!("{:.3}", row.tx.confirm_ms),
+                format!("{:.3}", row.tx.total_ms),
+                row.tx.block_number.map(|b| b.to_string()).unwrap_or_default(),
+                row.tx.gas_used.map(|g| g.to_string()).unwrap_or_default(),
+                row.tx.status.clone(),
+            ];
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md_content = String::new();
+
+        // Title and testing information
+        md_content.push_str(&format!("# RPC Latency Test Results: {}\n\n",
+            self.test_name.as_deref().unwrap_or("Default")));
+
+        md_content.push_str("## Test Information\n\n");
+        md_content.push_str(&format!("- **Date and Time**: {}\n", self.timestamp.format("%Y-%m-%d %H:%M:%S UTC")));
+        md_content.push_str(&format!("- **RPC URL**: {}\n", self.rpc_url));
+        md_content.push_str(&format!("- **Chain ID**: {}\n", self.chain_id));
+        if self.senders.len() > 1 {
+            md_content.push_str(&format!("- **Sender Wallets**: {}\n", self.senders.len()));
+        } else {
+            md_content.push_str(&format!("- **Wallet**: {:?}\n", self.wallet));
+        }
+        if let Some(strategy) = &self.gas_strategy {
+            md_content.push_str(&format!("- **Gas Strategy**: {}\n", strategy));
+        }
+        md_content.push_str(&format!("- **Gas Price**: {} gwei\n", wei_as_gwei(&self.gas_price_wei)));
+        if let (Some(max_fee), Some(priority_fee)) = (&self.max_fee_per_gas_wei, &self.max_priority_fee_per_gas_wei) {
+            md_content.push_str(&format!("- **EIP-1559 Fees**: {} gwei max fee, {} gwei priority fee\n",
+                wei_as_gwei(max_fee), wei_as_gwei(priority_fee)));
+        }
+        md_content.push_str(&format!("- **Transaction Method**: {}\n", self.method));
+        if let Some(tx_type) = self.tx_type {
+            md_content.push_str(&format!("- **Transaction Type**: {}\n", tx_type));
+        }
+        if let Some(strategy) = &self.confirm_strategy {
+            md_content.push_str(&format!("- **Confirmation**: {}\n", strategy));
+        }
+        if let Some(concurrency) = self.concurrency {
+            md_content.push_str(&format!("- **Concurrency**: {} workers\n", concurrency));
+        }
+        md_content.push_str(&format!("- **Total Test Duration**: {:.0} ms\n", self.total_duration_ms));
+        if self.interim {
+            md_content.push_str("- **Status**: interim snapshot, the run was still in progress\n");
+        }
+        md_content.push_str(&format!("- **Number of Transactions**: {}\n", self.transactions.len()));
+        if self.failed_txs > 0 {
+            md_content.push_str(&format!("- **Failed Transactions**: {} (send errors, or not confirmed before the run ended)\n",
+                self.failed_txs));
+        }
+        md_content.push_str(&format!("- **Throughput**: {:.2} tx/s\n\n", self.throughput()));
+
+        // Summary statistics
+        md_content.push_str("## Summary Statistics\n\n");
+        md_content.push_str(&self.stats().to_markdown());
+        md_content.push('\n');
+
+        if self.senders.len() > 1 {
+            md_content.push_str("## Per Sender Statistics (total time)\n\n");
+            md_content.push_str("| Sender | TXs | P50 (ms) | P99 (ms) | Max (ms) | Avg (ms) |\n");
+            md_content.push_str("|--------|-----|----------|----------|----------|----------|\n");
+            for (sender, stats) in self.sender_stats() {
+                let total = &stats.total;
+                md_content.push_str(&format!("| `{:?}` | {} | {:.2} | {:.2} | {:.2} | {:.2} |\n",
+                    sender, total.count(), total.p50_ms(), total.p99_ms(), total.max_ms(), total.mean_ms()));
+            }
+            md_content.push('\n');
+        }
+
+        // Individual transactions
+        md_content.push_str("## Individual Transaction Results\n\n");
+        md_content.push_str("| TX# | Sender | Send (ms) | Confirm (ms) | Total (ms) | Block | Gas Used | Status | Hash |\n");
+        md_content.push_str("|-----|--------|-----------|--------------|------------|-------|----------|--------|------|\n");
+
+        for tx in &self.transactions {
+            md_content.push_str(&format!("| {} | `{:?}` | {:.2} | {:.2} | {:.2} | {} | {} | {} | `{:?}` |\n",
+                tx.index + 1,
+                tx.sender,
+                tx.send_ms,
+                tx.confirm_ms,
+                tx.total_ms,
+                tx.block_number.map(|b| b.to_string()).unwrap_or_default(),
+                tx.gas_used.map(|g| g.to_string()).unwrap_or_default(),
+                tx.status,
+                tx.hash
+            ));
+        }
+
+        md_content
+    }
+}
+
+/// One endpoint's run within a multi-endpoint benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointRun {
+    pub label: String,
+    pub report: RunReport,
+}
+
+/// The same workload run against several endpoints at the same time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub test_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub runs: Vec<EndpointRun>,
+}
+
+/// An endpoint's position in the benchmark ranking
+#[derive(Debug, Clone)]
+pub struct RankedEndpoint {
+    pub label: String,
+    pub rpc_url: String,
+    pub count: usize,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+}
+
+impl BenchReport {
+    /// Metric endpoints are ranked by: confirm time, or total time for
+    /// sync methods where confirmation happens inside the send call
+    pub fn ranking_metric(&self) -> Metric {
+        match self.runs.first() {
+            Some(run) if run.report.method.is_sync() => Metric::Total,
+            _ => Metric::Confirm,
+        }
+    }
+
+    /// Endpoints ordered by p50 then p99 of the ranking metric, fastest
+    /// first. Endpoints without a single confirmed transaction rank last.
+    pub fn ranking(&self) -> Vec<RankedEndpoint> {
+        let metric = self.ranking_metric();
+        let mut ranked = self
+            .runs
+            .iter()
+            .map(|run| {
+                let stats = run.report.stats();
+                let stats = stats.metric(metric);
+                let (p50_ms, p99_ms) = if stats.is_empty() {
+                    (f64::INFINITY, f64::INFINITY)
+                } else {
+                    (stats.p50_ms(), stats.p99_ms())
+                };
+                RankedEndpoint {
+                    label: run.label.clone(),
+                    rpc_url: run.report.rpc_url.clone(),
+                    count: run.report.transactions.len(),
+                    p50_ms,
+                    p99_ms,
+                }
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| a.p50_ms.total_cmp(&b.p50_ms).then(a.p99_ms.total_cmp(&b.p99_ms)));
+        ranked
+    }
+
+    pub fn file_stem(&self) -> String {
+        let timestamp = self.timestamp.format("%Y-%m-%d-%H%M%S");
+        match &self.test_name {
+            Some(test_name) => format!("{}-bench-{}", test_name, timestamp),
+            None => format!("bench-{}", timestamp),
+        }
+    }
+
+    /// Writes the combined report into `dir` once per format and returns the paths
+    pub fn save(&self, dir: &Path, formats: &[ReportFormat]) -> Result<Vec<PathBuf>> {
+        write_formats(dir, &self.file_stem(), formats, |format| self.render(format))
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Ndjson => {
+                let mut out = String::new();
+                for run in &self.runs {
+                    out.push_str(&run.report.to_ndjson()?);
+                }
+                out
+            }
+            ReportFormat::Csv => {
+                // Every run renders its own header; keep only the first one
+                let mut out = String::new();
+                for (i, run) in self.runs.iter().enumerate() {
+                    let csv = run.report.to_csv();
+                    let skip = if i == 0 { 0 } else { 1 };
+                    for line in csv.lines().skip(skip) {
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                out
+            }
+        })
+    }
+
+    /// Prints the ranking table to the console
+    pub fn print_ranking(&self) {
+        println!("\nENDPOINT RANKING (by {} p50, then p99):", self.ranking_metric().name().to_lowercase());
+        println!("{:<5} {:<24} {:>6} {:>12} {:>12}  RPC URL", "RANK", "ENDPOINT", "TXS", "P50 (ms)", "P99 (ms)");
+        println!("{}", "-".repeat(100));
+        for (i, endpoint) in self.ranking().iter().enumerate() {
+            println!("{:<5} {:<24} {:>6} {:>12.2} {:>12.2}  {}",
+                     i + 1, endpoint.label, endpoint.count, endpoint.p50_ms, endpoint.p99_ms, endpoint.rpc_url);
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str(&format!("# Multi-Endpoint Benchmark: {}\n\n",
+            self.test_name.as_deref().unwrap_or("Default")));
+        md.push_str(&format!("- **Date and Time**: {}\n", self.timestamp.format("%Y-%m-%d %H:%M:%S UTC")));
+        if let Some(run) = self.runs.first() {
+            md.push_str(&format!("- **Transaction Method**: {}\n", run.report.method));
+        }
+        md.push_str(&format!("- **Endpoints**: {}\n\n", self.runs.len()));
+
+        md.push_str(&format!("## Ranking (by {} p50, then p99)\n\n", self.ranking_metric().name().to_lowercase()));
+        md.push_str("| Rank | Endpoint | TXs | P50 (ms) | P99 (ms) | RPC URL |\n");
+        md.push_str("|------|----------|-----|----------|----------|---------|\n");
+        for (i, endpoint) in self.ranking().iter().enumerate() {
+            md.push_str(&format!("| {} | {} | {} | {:.2} | {:.2} | {} |\n",
+                i + 1, endpoint.label, endpoint.count, endpoint.p50_ms, endpoint.p99_ms, endpoint.rpc_url));
+        }
+
+        for run in &self.runs {
+            md.push_str(&format!("\n## {}\n\n", run.label));
+            md.push_str(&format!("- **RPC URL**: {}\n", run.report.rpc_url));
+            md.push_str(&format!("- **Chain ID**: {}\n", run.report.chain_id));
+            md.push_str(&format!("- **Wallet**: {:?}\n", run.report.wallet));
+            md.push_str(&format!("- **Total Test Duration**: {:.0} ms\n\n", run.report.total_duration_ms));
+            md.push_str(&run.report.stats().to_markdown());
+        }
+
+        md
+    }
+}
+
+/// Writes `<dir>/<stem>.<ext>` for every format, creating `dir` if needed
+fn write_formats<F>(dir: &Path, stem: &str, formats: &[ReportFormat], render: F) -> Result<Vec<PathBuf>>
+where
+    F: Fn(ReportFormat) -> Result<String>,
+{
+    // Create directory if it doesn't exist
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+
+    let mut paths = Vec::with_capacity(formats.len());
+    for format in formats {
+        let path = dir.join(format!("{}.{}", stem, format.extension()));
+        fs::write(&path, render(*format)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Converts milliseconds read from a report, saturating values too large
+/// for a `Duration` (a corrupted or hand-edited report) instead of panicking
+fn ms_duration(ms: f64) -> Duration {
+    Duration::try_from_secs_f64(ms.max(0.0) / 1000.0).unwrap_or(Duration::MAX)
+}
+
+/// Shows a decimal wei string as gwei, or as is when it doesn't parse.
+/// The caller adds the unit.
+fn wei_as_gwei(wei: &str) -> String {
+    U256::from_dec_str(wei).map_or_else(|_| wei.to_string(), format_gwei)
+}
+
+/// Quotes a CSV field if it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confirm::ConfirmMode;
+
+    fn report() -> RunReport {
+        RunReport {
+            test_name: Some("a,b".to_string()),
+            timestamp: DateTime::UNIX_EPOCH,
+            rpc_url: "http://localhost:8545".to_string(),
+            chain_id: 1,
+            wallet: Address::zero(),
+            senders: vec![Address::zero()],
+            method: Method::Async,
+            tx_type: Some(TxType::Eip1559),
+            gas_price_wei: "1500000000".to_string(),
+            gas_strategy: Some(GasStrategy::Fixed { max_fee_gwei: 2.0, priority_fee_gwei: 1.0 }),
+            max_fee_per_gas_wei: None,
+            max_priority_fee_per_gas_wei: None,
+            confirm_strategy: Some(ConfirmStrategy::new(ConfirmMode::Poll, Duration::from_millis(100))),
+            concurrency: None,
+            total_duration_ms: 1000.0,
+            interim: false,
+            failed_txs: 2,
+            transactions: vec![TxRecord {
+                index: 0,
+                hash: H256::zero(),
+                sender: Address::zero(),
+                nonce: 7,
+                send_ms: 1.0,
+                confirm_ms: 2.0,
+                total_ms: 3.0,
+                block_number: Some(10),
+                gas_used: Some(21000),
+                status: "success".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn quotes_csv_fields_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn csv_rows_carry_the_run_strategies() {
+        let csv = report().to_csv();
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert!(header.starts_with("test_name,timestamp,rpc_url,chain_id,method,tx_type,gas_strategy,confirm_strategy,failed_txs,"));
+        let row = lines.next().unwrap();
+        assert!(row.contains(",eip1559,\"fixed (2 gwei max fee, 1 gwei priority fee)\",poll (receipt every 100 ms),2,"), "{}", row);
+        assert!(row.starts_with("\"a,b\","), "{}", row);
+    }
+
+    #[test]
+    fn ndjson_rows_carry_the_run_strategies() {
+        let ndjson = report().to_ndjson().unwrap();
+        let row: serde_json::Value = serde_json::from_str(ndjson.lines().next().unwrap()).unwrap();
+        assert_eq!(row["tx_type"], "eip1559");
+        assert_eq!(row["gas_strategy"]["mode"], "fixed");
+        assert_eq!(row["confirm_strategy"]["mode"], "poll");
+        assert_eq!(row["nonce"], 7);
+        assert_eq!(row["failed_txs"], 2);
+    }
+
+    #[test]
+    fn reports_the_failed_transactions() {
+        let mut report = report();
+        assert!(report.to_markdown().contains("- **Failed Transactions**: 2 "));
+        report.failed_txs = 0;
+        assert!(!report.to_markdown().contains("Failed Transactions"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        let mut older = json.clone();
+        older.as_object_mut().unwrap().remove("failed_txs");
+        assert_eq!(serde_json::from_value::<RunReport>(older).unwrap().failed_txs, 0);
+    }
+
+    #[test]
+    fn wei_as_gwei_leaves_the_unit_to_the_caller() {
+        assert_eq!(wei_as_gwei("1500000000"), "1.5");
+        assert_eq!(wei_as_gwei("not a number"), "not a number");
+    }
+
+    #[test]
+    fn ms_duration_saturates_instead_of_panicking() {
+        assert_eq!(ms_duration(1500.0), Duration::from_millis(1500));
+        assert_eq!(ms_duration(-5.0), Duration::ZERO);
+        assert_eq!(ms_duration(f64::NAN), Duration::ZERO);
+        assert_eq!(ms_duration(f64::INFINITY), Duration::MAX);
+        assert_eq!(ms_duration(1e300), Duration::MAX);
+    }
+}
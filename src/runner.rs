@@ -0,0 +1,269 @@
+use anyhow::{anyhow, Result};
+use futures::future::try_join_all;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+    time::Instant,
+};
+use tokio::time::timeout_at;
+
+use crate::{
+    client::Connection,
+    confirm::Confirmer,
+    gas::Fees,
+    latency::{send_and_confirm_transaction, send_raw_sync},
+    method::Method,
+    nonce::{is_nonce_taken, pending_nonce, SharedNonces},
+    receipt,
+    report::TxRecord,
+    tx::{self, TxType},
+};
+
+/// Parameters of a sequential send-and-confirm run
+#[derive(Debug, Clone)]
+pub struct SequentialRun {
+    pub method: Method,
+    pub count: u64,
+    /// Index of the first transaction, so runs split across several
+    /// wallets number their transactions uniquely
+    pub first_index: u64,
+    pub starting_nonce: u64,
+    pub tx_type: TxType,
+    pub fees: Fees,
+    /// Waits for the receipts of async sends
+    pub confirmer: Confirmer,
+    /// Print every transaction and receipt as it completes
+    pub verbose: bool,
+}
+
+/// Parameters of a closed-loop run: `workers` workers each keep one
+/// transaction in flight until `count` transactions have been sent or the
+/// deadline passes
+#[derive(Debug, Clone)]
+pub struct ClosedLoopRun {
+    pub method: Method,
+    pub count: u64,
+    /// No transaction is started after this, and any still unconfirmed
+    /// then fails, for duration-based runs
+    pub deadline: Option<Instant>,
+    pub workers: u64,
+    pub tx_type: TxType,
+    pub fees: Fees,
+    /// Waits for the receipts of async sends
+    pub confirmer: Confirmer,
+}
+
+/// Sends `run.count` transactions one after another, waiting for each to
+/// confirm before sending the next. Failed async sends are logged and
+/// skipped, the next one going out at the node's pending nonce so no gap is
+/// left; a send rejected because its nonce is already used goes out again
+/// at the next one. Sync method failures abort the run. Records are pushed
+/// to `records` as they confirm, so they are kept if the run is aborted.
+pub async fn run_sequential(conn: &Connection, run: &SequentialRun, records: &mut Vec<TxRecord>) -> Result<()> {
+    let mut nonce = run.starting_nonce;
+    let tx = TxParams {
+        method: run.method,
+        tx_type: run.tx_type,
+        fees: &run.fees,
+        confirmer: &run.confirmer,
+        deadline: None,
+        verbose: run.verbose,
+    };
+
+    for n in 0..run.count {
+        let i = run.first_index + n;
+        loop {
+            match send_and_confirm(conn, &tx, i, nonce).await? {
+                Sent::Confirmed(record) => {
+                    records.push(record);
+                    nonce += 1;
+                }
+                Sent::NonceTaken => {
+                    nonce = pending_nonce(&conn.client, conn.address).await?.max(nonce + 1);
+                    continue;
+                }
+                Sent::Failed => nonce = pending_nonce(&conn.client, conn.address).await?.min(nonce + 1),
+            }
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `run.workers` workers over the senders, worker `w` sending from
+/// `conns[w % conns.len()]`. Every worker takes the next transaction index
+/// and its sender's next nonce, sends it and waits for it to confirm before
+/// taking another, so exactly `workers` transactions are in flight until
+/// the last ones. Failed async sends are logged and skipped, and a nonce the
+/// node never got is handed out again so it doesn't leave a gap; a send
+/// rejected because its nonce is already used goes out again at another
+/// nonce and isn't a failure. Sync method failures abort the run. Async
+/// transactions still unconfirmed at the deadline fail too, so a dropped
+/// transaction can't hold the run past it. Failures are counted in
+/// `failed`. Records are pushed to `records` in completion order as they
+/// confirm, so they can be read while the run goes on and are kept if it
+/// is aborted.
+pub async fn run_closed_loop(
+    conns: &[Connection],
+    starting_nonces: &[u64],
+    run: &ClosedLoopRun,
+    records: &Mutex<Vec<TxRecord>>,
+    failed: &AtomicU64,
+) -> Result<()> {
+    let next_index = AtomicU64::new(0);
+    let sender_nonces = starting_nonces.iter().map(|nonce| SharedNonces::new(*nonce)).collect::<Vec<_>>();
+    let tx = TxParams {
+        method: run.method,
+        tx_type: run.tx_type,
+        fees: &run.fees,
+        confirmer: &run.confirmer,
+        deadline: run.deadline,
+        verbose: run.workers == 1,
+    };
+
+    let workers = (0..run.workers).map(|worker| {
+        let sender = worker as usize % conns.len();
+        let (conn, nonces) = (&conns[sender], &sender_nonces[sender]);
+        let (next_index, tx) = (&next_index, &tx);
+        async move {
+            loop {
+                let i = next_index.fetch_add(1, Ordering::Relaxed);
+                if i >= run.count {
+                    return Ok::<_, anyhow::Error>(());
+                }
+                loop {
+                    if run.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return Ok(());
+                    }
+                    let nonce = nonces.take();
+                    match send_and_confirm(conn, tx, i, nonce).await? {
+                        Sent::Confirmed(record) => records.lock().unwrap().push(record),
+                        Sent::NonceTaken => continue,
+                        Sent::Failed => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            // Every later nonce of the sender queues behind
+                            // one the node doesn't hold, so it goes out again.
+                            // Nonces other workers have in flight are left
+                            // alone.
+                            if pending_nonce(&conn.client, conn.address).await? <= nonce {
+                                nonces.reissue(nonce);
+                            }
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    try_join_all(workers).await?;
+    Ok(())
+}
+
+/// How every transaction of a run is sent
+struct TxParams<'a> {
+    method: Method,
+    tx_type: TxType,
+    fees: &'a Fees,
+    confirmer: &'a Confirmer,
+    /// Async sends not confirmed by then fail
+    deadline: Option<Instant>,
+    /// Print the transaction and receipt as it completes
+    verbose: bool,
+}
+
+/// How a transaction of a run went
+enum Sent {
+    Confirmed(TxRecord),
+    /// The node rejected the send because the nonce is already used
+    NonceTaken,
+    /// The async send failed or wasn't confirmed by the deadline
+    Failed,
+}
+
+/// Sends transaction `i` at `nonce` and waits for it to confirm. Async
+/// send failures are logged and returned, sync method failures are errors.
+async fn send_and_confirm(conn: &Connection, params: &TxParams<'_>, i: u64, nonce: u64) -> Result<Sent> {
+    let client = conn.client.clone();
+    let wallet_address = conn.address;
+    let method = params.method;
+
+    if params.verbose {
+        println!("\n--- Transaction #{} (nonce: {}) ---", i + 1, nonce);
+    }
+
+    // Start timing total transaction time
+    let tx_start = Instant::now();
+
+    let record = if method == Method::Async {
+        // Use regular async transaction method
+        let tx = tx::self_transfer(params.tx_type, wallet_address, Some(nonce), params.fees);
+        let confirmed = send_and_confirm_transaction(client.clone(), tx, params.confirmer, params.verbose);
+        let result = match params.deadline {
+            Some(deadline) => timeout_at(deadline.into(), confirmed)
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("not confirmed before the end of the run"))),
+            None => confirmed.await,
+        };
+        match result {
+            Ok((receipt, send_time, confirm_time)) => {
+                let total_time = tx_start.elapsed();
+                if params.verbose {
+                    println!("TX #{}: total time: {:?} (send: {:?}, confirm: {:?})",
+                             i + 1, total_time, send_time, confirm_time);
+                }
+
+                Sent::Confirmed(TxRecord::new(i, nonce, &receipt, send_time, confirm_time, total_time))
+            },
+            Err(e) if is_nonce_taken(&e) => {
+                println!("TX #{}: nonce {} already used, sending again at another", i + 1, nonce);
+                Sent::NonceTaken
+            }
+            Err(e) => {
+                println!("TX #{}: error: {}", i + 1, e);
+                Sent::Failed
+            }
+        }
+    } else {
+        // Create transaction with explicit nonce and the run's fees,
+        // of the same type the async method sends
+        let tx = tx::signable_self_transfer(params.tx_type, wallet_address, conn.chain_id.as_u64(), nonce, params.fees);
+
+        // Start measuring send time
+        let send_start = Instant::now();
+
+        // Sign the transaction and get the EIP-2718 encoded bytes
+        let raw_tx = tx::sign_raw(&client, &tx).await?;
+
+        let confirm_time = Duration::default();  // Not applicable for sync methods
+
+        if params.verbose {
+            println!("Sending TX #{} with {}...", i + 1, method.rpc_method());
+        }
+        let receipt = send_raw_sync(client.clone(), method, raw_tx).await?;
+        let send_time = send_start.elapsed();
+
+        let total_time = tx_start.elapsed();
+
+        if params.verbose {
+            receipt::print_receipt(&receipt);
+            receipt::print_inclusion(&receipt);
+
+            println!("TX #{}: total time: {:?} (send: {:?})",
+                   i + 1, total_time, send_time);
+        }
+
+        // For sync methods, send time is the total time (confirm time is 0)
+        Sent::Confirmed(TxRecord::new(i, nonce, &receipt, send_time, confirm_time, total_time))
+    };
+
+    if params.verbose {
+        println!("--- End Transaction #{} ---\n", i + 1);
+    }
+
+    Ok(record)
+}
@@ -0,0 +1,326 @@
+use clap::ValueEnum;
+use hdrhistogram::Histogram;
+use std::time::Duration;
+
+/// Significant figures kept by the histogram (0.1% relative error)
+const SIGFIG: u8 = 3;
+
+/// Highest trackable latency in microseconds (1 hour); larger values saturate
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+
+/// Which per-transaction timing a comparison looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    Send,
+    Confirm,
+    Total,
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Send => "Send time",
+            Metric::Confirm => "Confirm time",
+            Metric::Total => "Total time",
+        }
+    }
+}
+
+/// Latency distribution for one metric (send, confirm or total time).
+/// Values are recorded in microseconds and reported in milliseconds.
+#[derive(Clone)]
+pub struct LatencyStats {
+    histogram: Histogram<u64>,
+}
+
+impl LatencyStats {
+    pub fn new() -> Self {
+        Self {
+            histogram: Histogram::new_with_bounds(1, MAX_LATENCY_US, SIGFIG)
+                .expect("histogram bounds are valid"),
+        }
+    }
+
+    pub fn from_durations<I: IntoIterator<Item = Duration>>(durations: I) -> Self {
+        let mut stats = Self::new();
+        for duration in durations {
+            stats.record(duration);
+        }
+        stats
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        self.histogram.saturating_record(duration.as_micros() as u64);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    pub fn min_ms(&self) -> f64 {
+        us_to_ms(self.histogram.min())
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        us_to_ms(self.histogram.max())
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        self.histogram.mean() / 1000.0
+    }
+
+    pub fn stddev_ms(&self) -> f64 {
+        self.histogram.stdev() / 1000.0
+    }
+
+    /// Value at `percentile` (0-100) in milliseconds
+    pub fn percentile_ms(&self, percentile: f64) -> f64 {
+        us_to_ms(self.histogram.value_at_quantile(percentile / 100.0))
+    }
+
+    pub fn p50_ms(&self) -> f64 {
+        self.percentile_ms(50.0)
+    }
+
+    pub fn p90_ms(&self) -> f64 {
+        self.percentile_ms(90.0)
+    }
+
+    pub fn p99_ms(&self) -> f64 {
+        self.percentile_ms(99.0)
+    }
+
+    pub fn p999_ms(&self) -> f64 {
+        self.percentile_ms(99.9)
+    }
+
+    /// Renders the distribution as `buckets` equal-width rows between min
+    /// and max, with bars scaled to `width` characters
+    pub fn ascii_histogram(&self, buckets: usize, width: usize) -> String {
+        if self.is_empty() || buckets == 0 {
+            return String::from("(no samples)\n");
+        }
+
+        let min = self.histogram.min();
+        let max = self.histogram.max();
+        // Samples the histogram can't tell apart make a single row
+        let max = if self.histogram.equivalent(min, max) { min } else { max };
+        let bucket_width = ((max - min) / buckets as u64).max(1);
+        let buckets = buckets.min(((max - min) / bucket_width + 1) as usize);
+
+        let mut counts = vec![0u64; buckets];
+        for value in self.histogram.iter_recorded() {
+            let index = (value.value_iterated_to().saturating_sub(min) / bucket_width) as usize;
+            counts[index.min(buckets - 1)] += value.count_at_value();
+        }
+
+        let peak = counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut out = String::new();
+        for (i, count) in counts.iter().enumerate() {
+            let low = min + bucket_width * i as u64;
+            let high = if i == buckets - 1 { max } else { low + bucket_width };
+            let bar = "#".repeat(((*count * width as u64) / peak) as usize);
+            out.push_str(&format!(
+                "{:>10.2} - {:<10.2} | {:<width$} {}\n",
+                us_to_ms(low),
+                us_to_ms(high),
+                bar,
+                count,
+                width = width
+            ));
+        }
+        out
+    }
+}
+
+impl Default for LatencyStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send, confirm and total time distributions for one run
+#[derive(Clone, Default)]
+pub struct RunStats {
+    pub send: LatencyStats,
+    pub confirm: LatencyStats,
+    pub total: LatencyStats,
+}
+
+impl RunStats {
+    /// Builds the stats from `(send, confirm, total)` timings
+    pub fn from_timings<I: IntoIterator<Item = (Duration, Duration, Duration)>>(timings: I) -> Self {
+        let mut stats = Self::default();
+        for (send, confirm, total) in timings {
+            stats.send.record(send);
+            stats.confirm.record(confirm);
+            stats.total.record(total);
+        }
+        stats
+    }
+
+    pub fn metric(&self, metric: Metric) -> &LatencyStats {
+        match metric {
+            Metric::Send => &self.send,
+            Metric::Confirm => &self.confirm,
+            Metric::Total => &self.total,
+        }
+    }
+
+    fn metrics(&self) -> [(&'static str, &LatencyStats); 3] {
+        [
+            ("Send time", &self.send),
+            ("Confirm time", &self.confirm),
+            ("Total time", &self.total),
+        ]
+    }
+
+    /// Prints the percentile table and histograms to the console
+    pub fn print(&self) {
+        print_table("LATENCY STATISTICS (ms):", &self.metrics());
+
+        for (name, stats) in self.metrics() {
+            println!("\n{} histogram (ms):", name);
+            print!("{}", stats.ascii_histogram(10, 40));
+        }
+    }
+
+    /// Renders the percentile table and histograms as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str("| Metric | Min (ms) | P50 (ms) | P90 (ms) | P99 (ms) | P99.9 (ms) | Max (ms) | Avg (ms) | StdDev (ms) |\n");
+        md.push_str("|--------|----------|----------|----------|----------|------------|----------|----------|-------------|\n");
+        for (name, stats) in self.metrics() {
+            md.push_str(&format!(
+                "| {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} |\n",
+                name,
+                stats.min_ms(),
+                stats.p50_ms(),
+                stats.p90_ms(),
+                stats.p99_ms(),
+                stats.p999_ms(),
+                stats.max_ms(),
+                stats.mean_ms(),
+                stats.stddev_ms()
+            ));
+        }
+
+        for (name, stats) in self.metrics() {
+            md.push_str(&format!("\n### {} Histogram (ms)\n\n```\n", name));
+            md.push_str(&stats.ascii_histogram(10, 40));
+            md.push_str("```\n");
+        }
+        md
+    }
+}
+
+/// Prints a percentile table with one row per named distribution
+pub fn print_table(title: &str, rows: &[(&str, &LatencyStats)]) {
+    println!("\n{}", title);
+    println!(
+        "{:<18} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "", "MIN", "P50", "P90", "P99", "P99.9", "MAX", "AVG", "STDDEV"
+    );
+    println!("{}", "-".repeat(100));
+    for (name, stats) in rows {
+        println!(
+            "{:<18} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+            format!("{}:", name),
+            stats.min_ms(),
+            stats.p50_ms(),
+            stats.p90_ms(),
+            stats.p99_ms(),
+            stats.p999_ms(),
+            stats.max_ms(),
+            stats.mean_ms(),
+            stats.stddev_ms()
+        );
+    }
+}
+
+fn us_to_ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        // The histogram keeps 3 significant figures
+        assert!((actual - expected).abs() <= expected * 0.001, "{} is not close to {}", actual, expected);
+    }
+
+    /// Counts of the histogram rows, the last number of every line
+    fn row_counts(histogram: &str) -> Vec<u64> {
+        histogram
+            .lines()
+            .map(|line| line.split_whitespace().last().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn percentiles_of_a_uniform_distribution() {
+        let stats = LatencyStats::from_durations((1..=1000).map(ms));
+        assert_eq!(stats.count(), 1000);
+        assert_close(stats.min_ms(), 1.0);
+        assert_close(stats.p50_ms(), 500.0);
+        assert_close(stats.p90_ms(), 900.0);
+        assert_close(stats.p99_ms(), 990.0);
+        // Lands on the 999th or the 1000th sample depending on rounding
+        assert!((999.0..=1001.0).contains(&stats.p999_ms()), "{}", stats.p999_ms());
+        assert_close(stats.max_ms(), 1000.0);
+        assert_close(stats.mean_ms(), 500.5);
+    }
+
+    #[test]
+    fn latencies_above_the_maximum_saturate() {
+        let stats = LatencyStats::from_durations([ms(10), Duration::from_secs(7200)]);
+        assert_eq!(stats.count(), 2);
+        assert_close(stats.max_ms(), MAX_LATENCY_US as f64 / 1000.0);
+        assert_close(stats.min_ms(), 10.0);
+    }
+
+    #[test]
+    fn histogram_of_nothing() {
+        assert_eq!(LatencyStats::new().ascii_histogram(10, 20), "(no samples)\n");
+        let stats = LatencyStats::from_durations([ms(1)]);
+        assert_eq!(stats.ascii_histogram(0, 20), "(no samples)\n");
+    }
+
+    #[test]
+    fn histogram_of_a_single_sample() {
+        let histogram = LatencyStats::from_durations([ms(5)]).ascii_histogram(10, 20);
+        assert_eq!(row_counts(&histogram), vec![1]);
+        assert!(histogram.contains(&"#".repeat(20)), "{}", histogram);
+    }
+
+    #[test]
+    fn histogram_of_equal_samples_has_one_row() {
+        let histogram = LatencyStats::from_durations([ms(5); 4]).ascii_histogram(10, 20);
+        assert_eq!(row_counts(&histogram), vec![4]);
+    }
+
+    #[test]
+    fn histogram_never_has_more_rows_than_values() {
+        let stats = LatencyStats::from_durations((1..=3).map(Duration::from_micros));
+        let histogram = stats.ascii_histogram(10, 20);
+        assert_eq!(row_counts(&histogram), vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn histogram_counts_every_sample_once() {
+        let stats = LatencyStats::from_durations((1..=1000).map(ms));
+        let counts = row_counts(&stats.ascii_histogram(7, 40));
+        assert_eq!(counts.len(), 7);
+        assert_eq!(counts.iter().sum::<u64>(), 1000);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    types::{Address, TransactionReceipt, U256},
+    utils::format_ether,
+};
+use futures::future::join_all;
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+use crate::{
+    client::Client,
+    confirm::Confirmer,
+    gas::Fees,
+    latency::send_transaction,
+    receipt,
+    tx::{self, TxType},
+};
+
+/// A plain value transfer, signed by `client`
+pub struct Transfer {
+    pub client: Arc<Client>,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub nonce: u64,
+    pub tx_type: TxType,
+    pub fees: Fees,
+}
+
+/// A submitted transfer and its receipt, or the error that stopped it
+pub struct TransferResult {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    /// Gas price charged when the receipt doesn't report one
+    pub gas_price: U256,
+    pub outcome: Result<TransactionReceipt>,
+}
+
+impl TransferResult {
+    pub fn succeeded(&self) -> bool {
+        matches!(&self.outcome, Ok(receipt) if receipt::status_str(receipt) == "SUCCESS")
+    }
+
+    /// Gas paid by the sender: gas used times the effective gas price
+    pub fn gas_cost(&self) -> U256 {
+        match &self.outcome {
+            Ok(receipt) => {
+                receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or(self.gas_price)
+            }
+            Err(_) => U256::zero(),
+        }
+    }
+}
+
+/// Submits every transfer at once and waits up to `receipt_timeout` for
+/// each receipt with `confirmer`'s strategy, so a dropped transfer fails
+/// instead of hanging the run
+pub async fn execute(
+    transfers: Vec<Transfer>,
+    confirmer: &Confirmer,
+    receipt_timeout: Duration,
+) -> Vec<TransferResult> {
+    join_all(transfers.into_iter().map(|transfer| async move {
+        let tx = tx::transfer(transfer.tx_type, transfer.to, transfer.value, Some(transfer.nonce), &transfer.fees);
+        let outcome = async {
+            let hash = send_transaction(transfer.client.clone(), tx).await?;
+            timeout(receipt_timeout, confirmer.wait(&transfer.client, hash))
+                .await
+                .map_err(|_| anyhow!("no receipt after {:?}, {:?} may have been dropped", receipt_timeout, hash))?
+        }
+        .await;
+
+        TransferResult {
+            from: transfer.from,
+            to: transfer.to,
+            value: transfer.value,
+            gas_price: transfer.fees.gas_price,
+            outcome,
+        }
+    }))
+    .await
+}
+
+/// Prints one row per transfer followed by the value and gas totals
+pub fn print_summary(results: &[TransferResult]) {
+    println!("\n{:<42} {:<42} {:>22} {:>22}  STATUS", "FROM", "TO", "VALUE (ETH)", "GAS COST (ETH)");
+    println!("{}", "-".repeat(140));
+
+    let mut total_value = U256::zero();
+    let mut total_gas = U256::zero();
+    let mut succeeded = 0;
+    for result in results {
+        let status = match &result.outcome {
+            Ok(receipt) => receipt::status_str(receipt).to_string(),
+            Err(e) => format!("ERROR: {}", e),
+        };
+        println!("{:<42} {:<42} {:>22} {:>22}  {}",
+                 format!("{:?}", result.from), format!("{:?}", result.to),
+                 format_ether(result.value), format_ether(result.gas_cost()), status);
+
+        if result.succeeded() {
+            succeeded += 1;
+            total_value += result.value;
+        }
+        total_gas += result.gas_cost();
+    }
+
+    println!("\n{}/{} transfers succeeded", succeeded, results.len());
+    println!("Total transferred: {} ETH", format_ether(total_value));
+    println!("Total gas cost: {} ETH", format_ether(total_gas));
+}
@@ -0,0 +1,193 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::{
+        Http, HttpClientError, Ipc, IpcError, JsonRpcClient, JsonRpcError, ProviderError, PubsubClient, RpcError, Ws,
+        WsClientError,
+    },
+    types::U256,
+};
+use futures::channel::mpsc::UnboundedReceiver;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+use std::fmt;
+use thiserror::Error;
+
+/// JSON-RPC transport of a client, picked from the endpoint so the same
+/// send and confirm code runs over HTTP, WebSocket or a local IPC socket
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+
+    #[error(transparent)]
+    Ws(#[from] WsClientError),
+
+    #[error(transparent)]
+    Ipc(#[from] IpcError),
+
+    #[error("subscriptions need a WebSocket or IPC endpoint, not HTTP")]
+    NoSubscriptions,
+}
+
+impl Transport {
+    /// Connects to `endpoint`: `ws://` and `wss://` URLs over WebSocket,
+    /// other URLs over HTTP, and an absolute or `./` path (e.g.
+    /// `/var/lib/reth/reth.ipc`) to the node's IPC socket
+    pub async fn connect(endpoint: &str) -> Result<Self> {
+        if is_ws_url(endpoint) {
+            let ws = Ws::connect(endpoint)
+                .await
+                .with_context(|| format!("failed to connect to {}", endpoint))?;
+            Ok(Transport::Ws(ws))
+        } else if is_ipc_path(endpoint) {
+            let ipc = Ipc::connect(endpoint)
+                .await
+                .with_context(|| format!("failed to connect to the IPC socket {}", endpoint))?;
+            Ok(Transport::Ipc(ipc))
+        } else if !endpoint.contains("://") {
+            bail!("{} is neither a URL with a scheme (e.g. http://{}) nor an IPC socket path (absolute or starting with ./)",
+                  endpoint, endpoint);
+        } else {
+            let http = endpoint.parse::<Http>().with_context(|| format!("invalid RPC URL {}", endpoint))?;
+            Ok(Transport::Http(http))
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Http(_) => f.write_str("HTTP"),
+            Transport::Ws(_) => f.write_str("WebSocket"),
+            Transport::Ipc(_) => f.write_str("IPC"),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Transport::Http(http) => Ok(http.request(method, params).await?),
+            Transport::Ws(ws) => Ok(ws.request(method, params).await?),
+            Transport::Ipc(ipc) => Ok(ipc.request(method, params).await?),
+        }
+    }
+}
+
+impl PubsubClient for Transport {
+    type NotificationStream = UnboundedReceiver<Box<RawValue>>;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            Transport::Http(_) => Err(TransportError::NoSubscriptions),
+            Transport::Ws(ws) => Ok(ws.subscribe(id)?),
+            Transport::Ipc(ipc) => Ok(ipc.subscribe(id)?),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            Transport::Http(_) => Err(TransportError::NoSubscriptions),
+            Transport::Ws(ws) => Ok(ws.unsubscribe(id)?),
+            Transport::Ipc(ipc) => Ok(ipc.unsubscribe(id)?),
+        }
+    }
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            TransportError::Http(e) => e.as_error_response(),
+            TransportError::Ws(e) => e.as_error_response(),
+            TransportError::Ipc(e) => e.as_error_response(),
+            TransportError::NoSubscriptions => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            TransportError::Http(e) => e.as_serde_error(),
+            TransportError::Ws(e) => e.as_serde_error(),
+            TransportError::Ipc(e) => e.as_serde_error(),
+            TransportError::NoSubscriptions => None,
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(src: TransportError) -> Self {
+        match src {
+            TransportError::Http(e) => e.into(),
+            TransportError::Ws(e) => e.into(),
+            TransportError::Ipc(e) => e.into(),
+            TransportError::NoSubscriptions => ProviderError::JsonRpcClientError(Box::new(src)),
+        }
+    }
+}
+
+/// Whether `url` is a WebSocket endpoint
+pub fn is_ws_url(url: &str) -> bool {
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// Whether `endpoint` is an IPC socket path rather than a URL. Only
+/// absolute and explicitly relative paths (and Windows named pipes) count,
+/// so a URL missing its scheme like `localhost:8545` is not mistaken for one.
+pub fn is_ipc_path(endpoint: &str) -> bool {
+    !endpoint.contains("://")
+        && (endpoint.starts_with('/')
+            || endpoint.starts_with("./")
+            || endpoint.starts_with("../")
+            || endpoint.starts_with(r"\\.\pipe\"))
+}
+
+/// Whether `endpoint` supports `eth_subscribe`
+pub fn supports_subscriptions(endpoint: &str) -> bool {
+    is_ws_url(endpoint) || is_ipc_path(endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_without_a_scheme_are_not_ipc_paths() {
+        for endpoint in ["localhost:8545", "127.0.0.1:8545", "geth.ipc", "http://localhost:8545", "ws://localhost:8546"] {
+            assert!(!is_ipc_path(endpoint), "{} counted as an IPC path", endpoint);
+        }
+    }
+
+    #[test]
+    fn socket_paths_and_named_pipes_are_ipc_paths() {
+        for endpoint in ["./geth.ipc", "../data/geth.ipc", "/tmp/x.ipc", r"\\.\pipe\geth.ipc"] {
+            assert!(is_ipc_path(endpoint), "{} not counted as an IPC path", endpoint);
+            assert!(supports_subscriptions(endpoint));
+        }
+    }
+
+    #[test]
+    fn websocket_urls() {
+        assert!(is_ws_url("ws://localhost:8546"));
+        assert!(is_ws_url("wss://mainnet.example.com/ws"));
+        assert!(supports_subscriptions("wss://mainnet.example.com/ws"));
+        for endpoint in ["http://localhost:8545", "https://example.com/ws", "localhost:8546"] {
+            assert!(!is_ws_url(endpoint), "{} counted as a WebSocket URL", endpoint);
+            assert!(!supports_subscriptions(endpoint));
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use ethers::{
+    core::types::Bytes,
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::{eip1559::Eip1559TransactionRequest, eip2718::TypedTransaction, eip2930::AccessList},
+        Address, TransactionRequest, U256,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{client::Client, gas::Fees};
+
+/// Gas limit of a plain ETH transfer
+pub const TRANSFER_GAS: u64 = 21000;
+
+/// Shape of the transactions a run sends. Every submission method sends
+/// the same type, so comparing methods only changes how they are submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxType {
+    /// Type 0, paying `gas_price`
+    Legacy,
+    /// Type 1, paying `gas_price` with an empty access list
+    Eip2930,
+    /// Type 2, paying the max fee and priority fee
+    Eip1559,
+}
+
+impl TxType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxType::Legacy => "legacy",
+            TxType::Eip2930 => "eip2930",
+            TxType::Eip1559 => "eip1559",
+        }
+    }
+}
+
+impl fmt::Display for TxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Builds a zero-value transaction to self.
+/// When `nonce` is `None` the signer middleware fills it in on send.
+pub fn self_transfer(tx_type: TxType, address: Address, nonce: Option<u64>, fees: &Fees) -> TypedTransaction {
+    transfer(tx_type, address, U256::zero(), nonce, fees)
+}
+
+/// Builds a transaction of `tx_type` sending `value` wei to `to`.
+/// When `nonce` is `None` the signer middleware fills it in on send.
+pub fn transfer(tx_type: TxType, to: Address, value: U256, nonce: Option<u64>, fees: &Fees) -> TypedTransaction {
+    let mut tx = match tx_type {
+        TxType::Legacy => TypedTransaction::Legacy(TransactionRequest::new().gas_price(fees.gas_price)),
+        TxType::Eip2930 => TypedTransaction::Eip2930(
+            TransactionRequest::new()
+                .gas_price(fees.gas_price)
+                .with_access_list(AccessList::default()),
+        ),
+        TxType::Eip1559 => TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas),
+        ),
+    };
+    tx.set_to(to);
+    tx.set_value(value);
+    if let Some(nonce) = nonce {
+        tx.set_nonce(nonce);
+    }
+
+    // Set fixed gas limit - 21000 is the cost of a simple ETH transfer
+    tx.set_gas(TRANSFER_GAS);
+
+    tx
+}
+
+/// Builds a fully populated zero-value transaction to self, ready for
+/// local signing
+pub fn signable_self_transfer(
+    tx_type: TxType,
+    address: Address,
+    chain_id: u64,
+    nonce: u64,
+    fees: &Fees,
+) -> TypedTransaction {
+    let mut tx = self_transfer(tx_type, address, Some(nonce), fees);
+    tx.set_from(address);
+    tx.set_chain_id(chain_id);
+    tx
+}
+
+/// Most a plain transfer of `tx_type` can cost at `fees`. Legacy and
+/// EIP-2930 transfers cost exactly this; EIP-1559 ones are charged the
+/// base fee plus priority fee, which can be less.
+pub fn transfer_cost(tx_type: TxType, fees: &Fees) -> U256 {
+    match tx_type {
+        TxType::Legacy | TxType::Eip2930 => fees.gas_price * TRANSFER_GAS,
+        TxType::Eip1559 => fees.max_fee_per_gas * TRANSFER_GAS,
+    }
+}
+
+/// Signs a transaction with the client's wallet and returns the
+/// EIP-2718 encoded bytes for `eth_sendRawTransaction`-style methods
+pub async fn sign_raw(client: &Client, tx: &TypedTransaction) -> Result<Bytes> {
+    let signature = client.signer().sign_transaction(tx).await?;
+    Ok(tx.rlp_signed(&signature))
+}
+
+/// Signs a fully populated transaction with `wallet` on the calling thread
+/// and returns its EIP-2718 encoded bytes, for signing in bulk off the
+/// async runtime
+pub fn sign_raw_with(wallet: &LocalWallet, tx: &TypedTransaction) -> Result<Bytes> {
+    let signature = wallet.sign_transaction_sync(tx)?;
+    Ok(tx.rlp_signed(&signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees() -> Fees {
+        Fees {
+            gas_price: U256::from(3_000_000_000u64),
+            max_fee_per_gas: U256::from(5_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000u64),
+        }
+    }
+
+    fn wallet() -> LocalWallet {
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcb78a13fa0ed8a2ff".parse().unwrap()
+    }
+
+    #[test]
+    fn builds_each_type_with_its_fees() {
+        let to = Address::repeat_byte(0x11);
+        let value = U256::from(7);
+
+        match transfer(TxType::Legacy, to, value, Some(3), &fees()) {
+            TypedTransaction::Legacy(tx) => assert_eq!(tx.gas_price, Some(fees().gas_price)),
+            tx => panic!("expected a legacy transaction, got {:?}", tx),
+        }
+        match transfer(TxType::Eip2930, to, value, Some(3), &fees()) {
+            TypedTransaction::Eip2930(tx) => {
+                assert_eq!(tx.tx.gas_price, Some(fees().gas_price));
+                assert!(tx.access_list.0.is_empty());
+            }
+            tx => panic!("expected an EIP-2930 transaction, got {:?}", tx),
+        }
+        match transfer(TxType::Eip1559, to, value, Some(3), &fees()) {
+            TypedTransaction::Eip1559(tx) => {
+                assert_eq!(tx.max_fee_per_gas, Some(fees().max_fee_per_gas));
+                assert_eq!(tx.max_priority_fee_per_gas, Some(fees().max_priority_fee_per_gas));
+            }
+            tx => panic!("expected an EIP-1559 transaction, got {:?}", tx),
+        }
+
+        for tx_type in [TxType::Legacy, TxType::Eip2930, TxType::Eip1559] {
+            let tx = transfer(tx_type, to, value, Some(3), &fees());
+            assert_eq!(tx.to_addr(), Some(&to));
+            assert_eq!(tx.value(), Some(&value));
+            assert_eq!(tx.nonce(), Some(&U256::from(3)));
+            assert_eq!(tx.gas(), Some(&U256::from(TRANSFER_GAS)));
+        }
+    }
+
+    #[test]
+    fn leaves_the_nonce_to_the_signer_when_unset() {
+        assert!(self_transfer(TxType::Eip1559, Address::zero(), None, &fees()).nonce().is_none());
+    }
+
+    #[test]
+    fn signable_transfers_are_to_self_and_fully_populated() {
+        let address = wallet().address();
+        let tx = signable_self_transfer(TxType::Eip1559, address, 1337, 9, &fees());
+        assert_eq!(tx.from(), Some(&address));
+        assert_eq!(tx.to_addr(), Some(&address));
+        assert_eq!(tx.chain_id(), Some(1337.into()));
+        assert_eq!(tx.value(), Some(&U256::zero()));
+    }
+
+    #[test]
+    fn signed_bytes_carry_the_type() {
+        let wallet = wallet().with_chain_id(1337u64);
+        let raw = |tx_type| {
+            let tx = signable_self_transfer(tx_type, wallet.address(), 1337, 0, &fees());
+            sign_raw_with(&wallet, &tx).unwrap()
+        };
+        // Typed transactions start with their type, legacy ones with an RLP list
+        assert!(raw(TxType::Legacy)[0] >= 0xc0);
+        assert_eq!(raw(TxType::Eip2930)[0], 0x01);
+        assert_eq!(raw(TxType::Eip1559)[0], 0x02);
+    }
+
+    #[test]
+    fn transfer_cost_uses_the_fee_the_type_pays() {
+        assert_eq!(transfer_cost(TxType::Legacy, &fees()), U256::from(63_000_000_000_000u64));
+        assert_eq!(transfer_cost(TxType::Eip2930, &fees()), U256::from(63_000_000_000_000u64));
+        assert_eq!(transfer_cost(TxType::Eip1559, &fees()), U256::from(105_000_000_000_000u64));
+        let free = Fees { gas_price: U256::zero(), max_fee_per_gas: U256::zero(), max_priority_fee_per_gas: U256::zero() };
+        assert!(transfer_cost(TxType::Eip1559, &free).is_zero());
+    }
+}
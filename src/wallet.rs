@@ -0,0 +1,165 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    signers::{coins_bip39::{English, Mnemonic}, LocalWallet},
+};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// A private key, mnemonic or password. Its `Debug` output is redacted so
+/// it can't leak through logs, errors or derived `Debug` impls.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// The secret itself, only to be handed to key parsing and decryption
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+/// BIP-44 path of Ethereum accounts; wallet `i` is derived at `<path>/i`
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+
+/// Derives `count` wallets from a BIP-39 mnemonic at `<path>/0` to
+/// `<path>/<count - 1>`. The seed and the base path are derived once, so
+/// deriving hundreds of wallets stays cheap. Errors never include the phrase.
+pub fn derive_wallets(phrase: &Secret, path: &str, count: u32) -> Result<Vec<LocalWallet>> {
+    if count == 0 {
+        bail!("at least one wallet must be derived from MNEMONIC");
+    }
+    let mnemonic = Mnemonic::<English>::new_from_phrase(phrase.expose().trim())
+        .map_err(|_| anyhow!("MNEMONIC is not a valid English BIP-39 phrase"))?;
+    let master = mnemonic
+        .master_key(None)
+        .map_err(|e| anyhow!("failed to derive the master key from MNEMONIC: {}", e))?;
+
+    let path = path.trim_end_matches('/');
+    let base = master
+        .derive_path(path)
+        .map_err(|e| anyhow!("invalid derivation path {}: {}", path, e))?;
+
+    (0..count)
+        .map(|index| {
+            // Relative to `base`, so only the last level is derived per wallet
+            let child = base
+                .derive_path(format!("m/{}", index).as_str())
+                .map_err(|e| anyhow!("failed to derive {}/{}: {}", path, index, e))?;
+            let key: &SigningKey = child.as_ref();
+            Ok(LocalWallet::from(key.clone()))
+        })
+        .collect()
+}
+
+/// Keystore files at `path`: the file itself, or every file in the
+/// directory sorted by name
+pub fn keystore_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = fs::read_dir(path)
+        .with_context(|| format!("failed to read keystore directory {}", path.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        bail!("keystore directory {} contains no files", path.display());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Decrypts Web3 Secret Storage keystore files with a shared password
+pub fn decrypt_keystores(files: &[PathBuf], password: &Secret) -> Result<Vec<LocalWallet>> {
+    files
+        .iter()
+        .map(|file| {
+            LocalWallet::decrypt_keystore(file, password.expose()).map_err(|e| {
+                anyhow!("failed to decrypt keystore {} (wrong password?): {}", file.display(), e)
+            })
+        })
+        .collect()
+}
+
+/// Reads a password file, ignoring the trailing newline
+pub fn read_password_file(path: &Path) -> Result<Secret> {
+    let password = fs::read_to_string(path)
+        .with_context(|| format!("failed to read password file {}", path.display()))?;
+    Ok(Secret::new(password.trim_end_matches(['\r', '\n'])))
+}
+
+/// Asks for the keystore password on the terminal without echoing it
+pub fn prompt_password(keystore: &Path) -> Result<Secret> {
+    rpassword::prompt_password(format!("Password for keystore {}: ", keystore.display()))
+        .map(Secret::from)
+        .context("failed to read the keystore password; use a password file when not running in a terminal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{signers::Signer, types::Address};
+
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    fn address(hex: &str) -> Address {
+        hex.parse().unwrap()
+    }
+
+    #[test]
+    fn derives_the_known_addresses() {
+        let wallets = derive_wallets(&Secret::from(PHRASE), DEFAULT_DERIVATION_PATH, 2).unwrap();
+        let addresses = wallets.iter().map(Signer::address).collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            vec![
+                address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"),
+                address("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_slash_in_the_path_is_ignored() {
+        let wallets = derive_wallets(&Secret::from(PHRASE), "m/44'/60'/0'/0/", 1).unwrap();
+        assert_eq!(wallets[0].address(), address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"));
+    }
+
+    #[test]
+    fn rejects_a_bad_path_or_count() {
+        let phrase = Secret::from(PHRASE);
+        assert!(derive_wallets(&phrase, "m/44'/sixty'", 1).is_err());
+        assert!(derive_wallets(&phrase, DEFAULT_DERIVATION_PATH, 0).is_err());
+    }
+
+    #[test]
+    fn errors_do_not_leak_the_phrase() {
+        let error = derive_wallets(&Secret::from("not a mnemonic"), DEFAULT_DERIVATION_PATH, 1).unwrap_err();
+        assert!(!error.to_string().contains("not a mnemonic"));
+    }
+}